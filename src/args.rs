@@ -1,7 +1,18 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
+use sw::{
+    column::Column,
+    entry::Kind,
+    filter::{parse_duration, parse_size},
+    glob::Pattern,
+    icons::IconSet,
+    output::{Field, Format},
+    quote::QuotingStyle,
+};
+
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -12,16 +23,179 @@ pub struct Cli {
     #[arg(short, long)]
     pub all: bool,
 
+    /// Display hidden files without the . and .. entries
+    #[arg(short = 'A', long)]
+    pub almost_all: bool,
+
+    /// Treat entries matching the glob PATTERN as hidden, can be repeated
+    #[arg(long, value_name = "PATTERN")]
+    pub hidden_pattern: Vec<Pattern>,
+
     /// Display the output in list format
     #[arg(short, long)]
     pub list: bool,
+
+    /// Columns of the list format, in order: perms, links, owner, group, size, mtime,
+    /// created, inode, device, context, mime, lines, name or git
+    /// [default: the config or perms,links,size,created,name]
+    #[arg(
+        long = "columns",
+        value_enum,
+        value_name = "COLUMNS",
+        value_delimiter = ','
+    )]
+    pub long_columns: Vec<Column>,
+
+    /// Show the inode number of each entry
+    #[arg(short, long)]
+    pub inode: bool,
+
+    /// Show the id of the device holding each entry, as major:minor
+    #[arg(long)]
+    pub device: bool,
+
+    /// List the names of the extended attributes below each entry of the list format
+    #[arg(long)]
+    pub xattr: bool,
+
+    /// Show the security context of each entry, read from security.selinux
+    #[arg(short = 'Z', long)]
+    pub context: bool,
+
+    /// Read the first bytes of the files to tell their type, which picks their icons
+    /// and colors and gets its own column in the list format
+    #[arg(long)]
+    pub mime: bool,
+
+    /// Count the lines of the text files, shown in their own column of the list format
+    /// and as a field of the other formats, ignored by the grid
+    #[arg(long)]
+    pub lines: bool,
+
+    /// Leave out the files larger than SIZE from the line counts
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "10M")]
+    pub lines_max: u64,
+
+    /// Append an indicator to the names: / for directories, * for executables,
+    /// @ for symbolic links, | for pipes and = for sockets
+    #[arg(short = 'F', long)]
+    pub classify: bool,
+
+    /// Append / to the names of directories
+    #[arg(short = 'p')]
+    pub slash: bool,
+
+    /// Print the number of folders and files and their total size at the end,
+    /// also written by the json and ndjson formats
+    #[arg(long)]
+    pub summary: bool,
+
+    /// Print a header above the columns of the list format
+    #[arg(long)]
+    pub header: bool,
+
+    /// Do not list entries matching the glob PATTERN, can be repeated
+    #[arg(long, value_name = "PATTERN")]
+    pub ignore: Vec<Pattern>,
+
+    /// Only list entries matching the glob PATTERN, can be repeated
+    #[arg(long, value_name = "PATTERN")]
+    pub only: Vec<Pattern>,
+
+    /// Do not list backup entries ending with ~
+    #[arg(short = 'B', long)]
+    pub ignore_backups: bool,
+
+    /// Only list entries of the given TYPEs: f, d, l, p, s, b or c
+    #[arg(long = "type", value_enum, value_name = "TYPE", value_delimiter = ',')]
+    pub types: Vec<Kind>,
+
+    /// Only list files larger than SIZE, such as 500k or 1G
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub larger: Option<u64>,
+
+    /// Only list files smaller than SIZE, such as 500k or 1G
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub smaller: Option<u64>,
+
+    /// Only list entries modified within DURATION, such as 12h or 7d
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub newer: Option<Duration>,
+
+    /// Only list entries modified more than DURATION ago, such as 12h or 7d
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    pub older: Option<Duration>,
+
+    /// Do not list entries ignored by .gitignore or .git/info/exclude
+    #[arg(long)]
+    pub git_ignore: bool,
+
+    /// Show the git status of each entry, staged changes first and worktree changes second
+    #[arg(long)]
+    pub git: bool,
+
+    /// List entries by columns (default)
+    #[arg(short = 'C', overrides_with = "across")]
+    pub columns: bool,
+
+    /// List entries by lines instead of by columns
+    #[arg(short = 'x', overrides_with = "columns")]
+    pub across: bool,
+
+    /// Output format, the others write every entry with its raw metadata
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Fields to write, in order, with the json, ndjson, csv and tsv formats [default: all]
+    #[arg(long, value_enum, value_name = "FIELDS", value_delimiter = ',')]
+    pub fields: Vec<Field>,
+
+    /// When to display icons
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t = IconMode::Auto)]
+    pub icons: IconMode,
+
+    /// Glyphs to use for icons, use unicode or ascii when a nerd font is not available
+    #[arg(long, value_enum, value_name = "SET", default_value_t = IconSet::Nerd)]
+    pub icon_set: IconSet,
+
+    /// How to quote names [default: shell-escape for terminals, literal otherwise]
+    #[arg(long, value_enum, value_name = "WORD")]
+    pub quoting_style: Option<QuotingStyle>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IconMode {
+    /// Display icons only when the output is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+impl IconMode {
+    #[must_use]
+    pub const fn enabled(self, terminal: bool) -> bool {
+        match self {
+            Self::Auto => terminal,
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::{path::PathBuf, time::Duration};
 
-    use crate::args::Cli;
+    use sw::{
+        column::Column,
+        entry::Kind,
+        glob::Pattern,
+        icons::IconSet,
+        output::{Field, Format},
+        quote::QuotingStyle,
+    };
+
+    use crate::args::{Cli, IconMode};
     use clap::{error::ErrorKind, Parser};
 
     #[test]
@@ -49,6 +223,179 @@ mod test {
         assert_eq!(cli.path, None);
         assert!(!cli.all);
         assert!(!cli.list);
+        assert_eq!(cli.icons, IconMode::Auto);
+        assert_eq!(cli.icon_set, IconSet::Nerd);
+        assert_eq!(cli.format, Format::Text);
+    }
+
+    #[test]
+    fn test_parse_args_icons() {
+        let args = vec!["myapp", "--icons=never", "--icon-set", "ascii"];
+        let cli = Cli::parse_from(args.clone());
+        assert_eq!(cli.icons, IconMode::Never);
+        assert_eq!(cli.icon_set, IconSet::Ascii);
+    }
+
+    #[test]
+    fn test_parse_args_direction() {
+        let cli = Cli::parse_from(vec!["myapp", "-x"]);
+        assert!(cli.across);
+
+        let cli = Cli::parse_from(vec!["myapp", "-x", "-C"]);
+        assert!(cli.columns);
+        assert!(!cli.across);
+    }
+
+    #[test]
+    fn test_parse_args_filters() {
+        let args = vec![
+            "myapp",
+            "--ignore",
+            "target",
+            "--ignore=*.o",
+            "--only",
+            "*.rs",
+            "-B",
+        ];
+        let cli = Cli::parse_from(args);
+        assert_eq!(
+            cli.ignore,
+            vec![Pattern::new("target"), Pattern::new("*.o")]
+        );
+        assert_eq!(cli.only, vec![Pattern::new("*.rs")]);
+        assert!(cli.ignore_backups);
+    }
+
+    #[test]
+    fn test_parse_args_hidden() {
+        let cli = Cli::parse_from(vec![
+            "myapp",
+            "-A",
+            "--hidden-pattern",
+            "__pycache__",
+            "--hidden-pattern=*.swp",
+        ]);
+        assert!(cli.almost_all);
+        assert!(!cli.all);
+        assert_eq!(
+            cli.hidden_pattern,
+            vec![Pattern::new("__pycache__"), Pattern::new("*.swp")]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_metadata_filters() {
+        let cli = Cli::parse_from(vec![
+            "myapp",
+            "--type=f,l",
+            "--type",
+            "p",
+            "--larger",
+            "1G",
+            "--older=7d",
+        ]);
+        assert_eq!(cli.types, vec![Kind::File, Kind::Symlink, Kind::Pipe]);
+        assert_eq!(cli.larger, Some(1 << 30));
+        assert_eq!(cli.smaller, None);
+        assert_eq!(cli.older, Some(Duration::from_secs(7 * 24 * 3600)));
+
+        let result = Cli::try_parse_from(vec!["myapp", "--larger", "lots"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_args_format() {
+        let cli = Cli::parse_from(vec!["myapp", "--format=json"]);
+        assert_eq!(cli.format, Format::Json);
+        assert!(cli.fields.is_empty());
+
+        let cli = Cli::parse_from(vec![
+            "myapp",
+            "--format",
+            "csv",
+            "--fields=name,size,link-target",
+        ]);
+        assert_eq!(cli.format, Format::Csv);
+        assert_eq!(
+            cli.fields,
+            vec![Field::Name, Field::Size, Field::LinkTarget]
+        );
+    }
+
+    #[test]
+    fn test_parse_args_columns() {
+        let cli = Cli::parse_from(vec!["myapp", "-l", "--columns=name,size", "--header"]);
+        assert_eq!(cli.long_columns, vec![Column::Name, Column::Size]);
+        assert!(cli.header);
+
+        let result = Cli::try_parse_from(vec!["myapp", "--columns=perms,colour"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_args_inode() {
+        let cli = Cli::parse_from(vec!["myapp", "-i", "--device", "--summary"]);
+        assert!(cli.inode);
+        assert!(cli.device);
+        assert!(cli.summary);
+    }
+
+    #[test]
+    fn test_parse_args_indicators() {
+        let cli = Cli::parse_from(vec!["myapp", "-F"]);
+        assert!(cli.classify);
+        assert!(!cli.slash);
+
+        let cli = Cli::parse_from(vec!["myapp", "--classify", "-p"]);
+        assert!(cli.classify);
+        assert!(cli.slash);
+    }
+
+    #[test]
+    fn test_parse_args_xattr() {
+        let cli = Cli::parse_from(vec!["myapp", "-lZ", "--xattr"]);
+        assert!(cli.list);
+        assert!(cli.context);
+        assert!(cli.xattr);
+        assert!(!cli.mime);
+
+        let cli = Cli::parse_from(vec!["myapp", "--mime", "--columns=mime,name"]);
+        assert!(cli.mime);
+        assert_eq!(cli.long_columns, vec![Column::Mime, Column::Name]);
+    }
+
+    #[test]
+    fn test_parse_args_lines() {
+        let cli = Cli::parse_from(vec!["myapp", "-l", "--lines"]);
+        assert!(cli.lines);
+        assert_eq!(cli.lines_max, 10 << 20);
+
+        let cli = Cli::parse_from(vec!["myapp", "--lines", "--lines-max=500k"]);
+        assert_eq!(cli.lines_max, 500 << 10);
+    }
+
+    #[test]
+    fn test_parse_args_git() {
+        let cli = Cli::parse_from(vec!["myapp", "--git", "--git-ignore"]);
+        assert!(cli.git);
+        assert!(cli.git_ignore);
+    }
+
+    #[test]
+    fn test_parse_args_quoting_style() {
+        let cli = Cli::parse_from(vec!["myapp", "--quoting-style=c"]);
+        assert_eq!(cli.quoting_style, Some(QuotingStyle::C));
+
+        let cli = Cli::parse_from(vec!["myapp", "--quoting-style", "shell-escape"]);
+        assert_eq!(cli.quoting_style, Some(QuotingStyle::ShellEscape));
+    }
+
+    #[test]
+    fn test_icon_mode_enabled() {
+        assert!(IconMode::Auto.enabled(true));
+        assert!(!IconMode::Auto.enabled(false));
+        assert!(IconMode::Always.enabled(false));
+        assert!(!IconMode::Never.enabled(true));
     }
 
     #[test]
@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+
+/// A column of the long format
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Perms,
+    Links,
+    Owner,
+    Group,
+    Size,
+    /// Time of the last modification
+    Mtime,
+    /// Time of creation
+    Created,
+    Inode,
+    /// Major and minor number of the device holding the entry
+    Device,
+    /// `SELinux` security context
+    Context,
+    /// Type of the files told by their first bytes
+    Mime,
+    /// Line count of the text files
+    Lines,
+    Name,
+    /// Staged and worktree changes, added before the name with `--git`
+    Git,
+}
+
+impl Column {
+    /// The columns of the long format when neither the command line nor the config picks them
+    pub const DEFAULT: [Self; 5] = [
+        Self::Perms,
+        Self::Links,
+        Self::Size,
+        Self::Created,
+        Self::Name,
+    ];
+
+    #[must_use]
+    pub const fn header(self) -> &'static str {
+        match self {
+            Self::Perms => "Permissions",
+            Self::Links => "Links",
+            Self::Owner => "Owner",
+            Self::Group => "Group",
+            Self::Size => "Size",
+            Self::Mtime => "Modified",
+            Self::Created => "Created",
+            Self::Inode => "Inode",
+            Self::Device => "Device",
+            Self::Context => "Context",
+            Self::Mime => "Type",
+            Self::Lines => "Lines",
+            Self::Name => "Name",
+            Self::Git => "Git",
+        }
+    }
+
+    /// Numbers line up on the right, everything else on the left
+    #[must_use]
+    pub const fn right_aligned(self) -> bool {
+        matches!(
+            self,
+            Self::Links | Self::Size | Self::Inode | Self::Device | Self::Lines
+        )
+    }
+
+    /// Parses a comma separated list of columns such as `perms,size,name`
+    ///
+    /// # Errors
+    /// Will return an error naming the first column that does not exist
+    pub fn parse_list(list: &str) -> Result<Vec<Self>, String> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| Self::from_str(name, true).map_err(|_| format!("Invalid column: {name}")))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::column::Column;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(
+            Column::parse_list("perms, size,NAME"),
+            Ok(vec![Column::Perms, Column::Size, Column::Name])
+        );
+        assert_eq!(Column::parse_list(""), Ok(vec![]));
+        assert_eq!(
+            Column::parse_list("perms,colour"),
+            Err(String::from("Invalid column: colour"))
+        );
+    }
+
+    #[test]
+    fn test_right_aligned() {
+        assert!(Column::Size.right_aligned());
+        assert!(!Column::Name.right_aligned());
+    }
+}
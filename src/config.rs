@@ -0,0 +1,96 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::column::Column;
+
+/// Defaults read from `$XDG_CONFIG_HOME/sw/config`, or `~/.config/sw/config`
+///
+/// The file holds `key = value` lines, `#` starts a comment:
+///
+/// ```text
+/// columns = perms,owner,size,mtime,name
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Columns of the long format
+    pub columns: Option<Vec<Column>>,
+}
+
+impl Config {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|directory| !directory.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+            .map(|directory| directory.join("sw").join("config"))
+    }
+
+    /// Reads the config file, a missing file is an empty config
+    ///
+    /// # Errors
+    /// Will return an error if the file has an invalid line
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+
+        fs::read_to_string(&path).map_or_else(
+            |_| Ok(Self::default()),
+            |contents| {
+                Self::parse(&contents).map_err(|e| format!("{}: {e}", path.to_string_lossy()))
+            },
+        )
+    }
+
+    /// # Errors
+    /// Will return an error for lines without `=`, unknown keys and invalid values
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(format!("line {}: expected key = value", number + 1));
+            };
+
+            match key.trim() {
+                "columns" => config.columns = Some(Column::parse_list(value)?),
+                key => return Err(format!("line {}: unknown key {key}", number + 1)),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{column::Column, config::Config};
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse("# defaults\n\ncolumns = perms, size,name # short\n")
+            .expect("Failed to parse");
+
+        assert_eq!(
+            config.columns,
+            Some(vec![Column::Perms, Column::Size, Column::Name])
+        );
+        assert_eq!(Config::parse(""), Ok(Config::default()));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Config::parse("columns").is_err());
+        assert!(Config::parse("colour = always").is_err());
+        assert!(Config::parse("columns = perms,nope").is_err());
+    }
+}
@@ -1,372 +1,828 @@
 use std::{
-    collections::BTreeSet,
-    io::{StdoutLock, Write},
-    path::Path,
+    ffi::{OsStr, OsString},
+    io::Write,
+    path::{Path, PathBuf},
 };
 
-use crate::{file::File, folder::Folder, support::get_file_name, Error, Result};
+use uzers::{Groups, Users, UsersCache};
+
+use crate::{
+    column::Column,
+    entry::{Entry, Indicator, Kind, Stat},
+    filter::Filter,
+    git::{GitStatus, Statuses},
+    grid::{Direction, Layout, Width},
+    icons::IconSet,
+    lister::Lister,
+    mime::Mime,
+    output::{write_ndjson, write_ndjson_summary, write_records, Field, Format, Record},
+    quote::{quote, QuotingStyle},
+    summary::Summary,
+    support::{
+        display_width, format_device, format_permissions, format_size, format_time, get_file_name,
+    },
+    Error, Result,
+};
 
 pub struct Directory {
-    pub cur_dir: Option<Folder>,
-    pub parent_dir: Option<Folder>,
-    pub folders: BTreeSet<Folder>,
-    pub hidden_folders: BTreeSet<Folder>,
-    pub files: BTreeSet<File>,
-    pub hidden_files: BTreeSet<File>,
-    largest_name: usize,
+    pub root: PathBuf,
+    /// `.` and `..`, only read with [`Hidden::All`]
+    pub dots: Vec<Entry>,
+    /// Folders before files, hidden ones first, each group sorted by name
+    pub entries: Vec<Entry>,
+    pub git: Option<Statuses>,
+    /// Why some entries could not be read, the listing goes on without them
+    pub errors: Vec<Error>,
 }
 
-impl Directory {
-    /// # Errors
-    /// This would throw an error if it cannot resolve any required objects
-    pub fn from(root: &Path, hidden: bool, list: bool) -> Result<Self> {
-        let mut folders = BTreeSet::new();
-        let mut hidden_folders = BTreeSet::new();
-        let mut files = BTreeSet::new();
-        let mut hidden_files = BTreeSet::new();
-
-        let mut largest_name = if hidden { 2 } else { 0 };
-
-        let (cur_dir, parent_dir) = if hidden {
-            (
-                Some(Folder::from(root, list)),
-                Some(root.parent().map_or_else(
-                    || Folder::from(root, list),
-                    |parent| Folder::from(parent, list),
-                )),
-            )
+/// Which hidden entries get listed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hidden {
+    Skip,
+    /// Hidden entries without `.` and `..`
+    AlmostAll,
+    /// Hidden entries along with `.` and `..`
+    All,
+}
+
+/// `true` lists hidden entries without `.` and `..`
+impl From<bool> for Hidden {
+    fn from(hidden: bool) -> Self {
+        if hidden {
+            Self::AlmostAll
         } else {
-            (None, None)
-        };
+            Self::Skip
+        }
+    }
+}
 
-        let Some(directory_to_read) = root.read_dir().ok() else {
-            return Err(Error::from("Cannot read the items in the directory"));
-        };
+/// How the directory gets printed
+#[allow(clippy::struct_excessive_bools)]
+pub struct DisplayOptions {
+    pub all: bool,
+    pub list: bool,
+    pub icons: Option<IconSet>,
+    pub direction: Direction,
+    pub quoting: QuotingStyle,
+    pub format: Format,
+    /// Fields of the machine readable formats, all of them when empty
+    pub fields: Vec<Field>,
+    /// Columns of the long format
+    pub columns: Vec<Column>,
+    /// Print a header above the columns of the long format
+    pub header: bool,
+    /// Show inode numbers in front of the names, or as the first long column
+    pub inode: bool,
+    /// Show device ids in front of the names, or as the first long column
+    pub device: bool,
+    /// List the extended attribute names below each entry of the long format
+    pub xattr: bool,
+    /// Show security contexts in front of the names, or before the long name column
+    pub context: bool,
+    /// Characters after the names telling their type
+    pub indicator: Indicator,
+    /// Show the sniffed type of the files before the long name column
+    pub mime: bool,
+    /// Show the line counts of the text files before the long name column
+    pub lines: bool,
+    /// Print the counts and total size after the entries
+    pub summary: bool,
+}
 
-        for item in directory_to_read {
-            let Ok(item) = item else {
-                return Err(Error::from("Cannot read the item in the directory"));
-            };
+impl DisplayOptions {
+    /// The long format gets printed with this column, picked or added by its option
+    #[must_use]
+    pub fn lists(&self, column: Column) -> bool {
+        let added = match column {
+            Column::Inode => self.inode,
+            Column::Device => self.device,
+            Column::Context => self.context,
+            Column::Mime => self.mime,
+            Column::Lines => self.lines,
+            _ => false,
+        };
 
-            let path = item.path();
+        self.format == Format::Text && self.list && (added || self.columns.contains(&column))
+    }
 
-            let name = get_file_name(&path);
+    /// One of the machine readable formats gets written with this field
+    #[must_use]
+    pub fn writes(&self, field: Field) -> bool {
+        self.format != Format::Text && (self.fields.is_empty() || self.fields.contains(&field))
+    }
 
-            if name.len() > largest_name {
-                largest_name = name.len();
+    fn icon(&self, entry: &Entry) -> String {
+        self.icons.map_or_else(String::new, |set| {
+            let name = entry.name.to_string_lossy();
+
+            if entry.locked {
+                format!("{} ", set.locked())
+            } else if entry.folder {
+                format!("{} ", set.folder(&name))
+            } else {
+                let icon = entry.mime.and_then(|mime| set.content(mime));
+                format!("{} ", icon.unwrap_or_else(|| set.file(&name)))
             }
+        })
+    }
 
-            if !hidden && name.chars().nth(0) == Some('.') {
-                continue;
-            }
+    fn name(&self, name: &OsStr) -> String {
+        quote(name, self.quoting)
+    }
+}
 
-            if hidden && name.chars().nth(0) == Some('.') {
-                if path.is_file() {
-                    hidden_files.insert(File::from(&path, list));
-                } else if path.is_dir() {
-                    hidden_folders.insert(Folder::from(&path, list));
-                }
-            } else if path.is_file() {
-                files.insert(File::from(&path, list));
-            } else if path.is_dir() {
-                folders.insert(Folder::from(&path, list));
-            }
-        }
+/// Color of the folders whose entries cannot be listed, in both layouts
+const LOCKED: &str = "31";
 
-        Ok(Self {
-            cur_dir,
-            parent_dir,
-            folders,
-            hidden_folders,
-            files,
-            hidden_files,
-            largest_name,
-        })
-    }
+/// Color of the files with an execute bit set, in both layouts
+const EXECUTABLE: &str = "1;33";
+
+/// The git status column, left out entirely when git statuses were not loaded
+fn git_column(status: Option<GitStatus>) -> String {
+    status.map_or_else(String::new, |status| format!("{status} "))
+}
 
-    const fn max_space(&self) -> usize {
-        self.largest_name + 4
+/// An entry of the grid layout along with the columns it takes up
+struct Cell {
+    text: String,
+    width: usize,
+}
+
+impl Cell {
+    fn new(
+        entry: &Entry,
+        options: &DisplayOptions,
+        icon_color: &str,
+        color: &str,
+        status: Option<GitStatus>,
+    ) -> Self {
+        let icon = options.icon(entry);
+        let name = options.name(&entry.name);
+        let indicator = entry
+            .indicator(options.indicator)
+            .map(String::from)
+            .unwrap_or_default();
+
+        Self {
+            text: format!(
+                "{}\x1B[{icon_color}m{icon}\x1B[0m\x1B[{color}m{name}\x1B[0m{indicator}",
+                git_column(status)
+            ),
+            width: status.map_or(0, |_| 3)
+                + display_width(&icon)
+                + display_width(&name)
+                + indicator.len(),
+        }
     }
+}
 
-    /// # Errors
-    /// Will return an error if it cannot print to stdout
-    pub fn display_output(
-        &self,
-        stdout: &mut StdoutLock,
-        width: usize,
-        all: bool,
-        list: bool,
-    ) -> Result<()> {
-        if list {
-            self.print_list(stdout, all)?;
-        } else {
-            self.print_nlist(stdout, width, all)?;
+/// An entry of the long format
+struct Line<'a> {
+    entry: &'a Entry,
+    status: Option<GitStatus>,
+}
+
+impl Line<'_> {
+    /// Owner and group names are looked up in `users`, only for their columns
+    fn cell(&self, column: Column, options: &DisplayOptions, users: &UsersCache) -> Cell {
+        let text = match column {
+            Column::Name if self.entry.locked => {
+                return Cell::new(self.entry, options, LOCKED, LOCKED, None);
+            }
+            Column::Name if self.entry.folder => {
+                return Cell::new(self.entry, options, "92", "1;32", None);
+            }
+            Column::Name if self.entry.is_executable() => {
+                return Cell::new(self.entry, options, "94", EXECUTABLE, None);
+            }
+            Column::Name => {
+                let color = self.entry.mime.and_then(Mime::color).unwrap_or("34");
+                return Cell::new(self.entry, options, "94", color, None);
+            }
+            Column::Git => {
+                return Cell {
+                    text: self
+                        .status
+                        .map_or_else(String::new, |status| status.to_string()),
+                    width: self.status.map_or(0, |_| 2),
+                };
+            }
+            Column::Perms => self.entry.stat.map_or_else(
+                || "?".repeat(10),
+                |stat| {
+                    let marker = self.entry.marker().map(String::from);
+                    format_permissions(stat.mode) + &marker.unwrap_or_default()
+                },
+            ),
+            Column::Context => self
+                .entry
+                .context
+                .clone()
+                .unwrap_or_else(|| String::from("?")),
+            Column::Mime if self.entry.kind.is_some_and(|kind| kind != Kind::File) => {
+                String::from("-")
+            }
+            Column::Mime => self.entry.mime.map_or("?", Mime::as_str).to_string(),
+            Column::Lines => self.field(|_| {
+                self.entry
+                    .lines
+                    .map_or_else(|| String::from("-"), |lines| lines.to_string())
+            }),
+            Column::Size if self.entry.locked => String::from("?"),
+            Column::Size if self.entry.folder => String::from("-"),
+            Column::Size => self.field(|stat| format_size(stat.size)),
+            Column::Created => format_time(self.entry.stat.and_then(|stat| stat.created)),
+            Column::Mtime => format_time(self.entry.stat.and_then(|stat| stat.modified)),
+            Column::Links => self.field(|stat| stat.links.to_string()),
+            Column::Inode => self.field(|stat| stat.inode.to_string()),
+            Column::Device => self.field(|stat| format_device(stat.device)),
+            Column::Owner => self.field(|stat| {
+                users.get_user_by_uid(stat.uid).map_or_else(
+                    || stat.uid.to_string(),
+                    |user| user.name().to_string_lossy().into_owned(),
+                )
+            }),
+            Column::Group => self.field(|stat| {
+                users.get_group_by_gid(stat.gid).map_or_else(
+                    || stat.gid.to_string(),
+                    |group| group.name().to_string_lossy().into_owned(),
+                )
+            }),
+        };
+
+        Cell {
+            width: display_width(&text),
+            text,
         }
+    }
 
-        Ok(())
+    /// A field of the metadata, `?` when it cannot be read
+    fn field(&self, format: impl Fn(&Stat) -> String) -> String {
+        self.entry
+            .stat
+            .as_ref()
+            .map_or_else(|| String::from("?"), format)
     }
+}
 
-    fn print_nlist(&self, stdout: &mut StdoutLock, width: usize, all: bool) -> Result<()> {
-        let mut count = 0;
+/// Reads the entry, or notes why it cannot be read and keeps it by name only
+fn read_entry(path: PathBuf, name: OsString, hidden: bool, errors: &mut Vec<Error>) -> Entry {
+    match Entry::new(path.clone(), name.clone(), hidden) {
+        Ok(entry) => entry,
+        Err(source) => {
+            errors.push(Error::Access {
+                path: path.clone(),
+                source,
+            });
+
+            Entry::unreadable(path, name, hidden)
+        }
+    }
+}
 
-        if !self.hidden_folders.is_empty() || !self.folders.is_empty() {
-            if all {
-                self.print_hidden_folders(stdout, &mut count, width)?;
+/// Reads the entries of `root` in the order the file system yields them and hands
+/// each one kept by `filter` to `each` right away, without `.` and `..`
+///
+/// # Errors
+/// Will return an error if the directory cannot be opened, or the first error of `each`
+pub fn read_each(
+    root: &Path,
+    hidden: Hidden,
+    filter: &Filter,
+    errors: &mut Vec<Error>,
+    mut each: impl FnMut(Entry) -> Result<()>,
+) -> Result<()> {
+    let directory_to_read = root.read_dir().map_err(|source| Error::OpenDirectory {
+        path: root.to_path_buf(),
+        source,
+    })?;
+
+    for item in directory_to_read {
+        let item = match item {
+            Ok(item) => item,
+            Err(source) => {
+                errors.push(Error::ReadDirectory {
+                    path: root.to_path_buf(),
+                    source,
+                });
+                continue;
             }
+        };
+
+        let path = item.path();
 
-            self.print_visible_folders(stdout, &mut count, width)?;
+        let name = get_file_name(&path);
+
+        let is_hidden = filter.is_hidden(&name);
+
+        if hidden == Hidden::Skip && is_hidden {
+            continue;
         }
 
-        if !self.hidden_files.is_empty() || !self.files.is_empty() {
-            if all {
-                self.print_hidden_files(stdout, &mut count, width)?;
-            }
+        // Only symbolic links need a stat to tell whether they lead to a folder
+        let is_dir = match item.file_type() {
+            Ok(file_type) if !file_type.is_symlink() => file_type.is_dir(),
+            _ => path.is_dir(),
+        };
+
+        if !filter.allows(&name, is_dir) {
+            continue;
+        }
 
-            self.print_visible_files(stdout, &mut count, width)?;
+        let entry = read_entry(path, name, is_hidden, errors);
 
-            match writeln!(stdout) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot display output in stdout")),
-            }
+        if filter.keeps(&entry) {
+            each(entry)?;
         }
-        Ok(())
     }
 
-    fn print_list(&self, stdout: &mut StdoutLock, all: bool) -> Result<()> {
-        if !self.hidden_folders.is_empty() || !self.folders.is_empty() {
-            if all {
-                self.print_hidden_folders_list(stdout)?;
-            }
+    Ok(())
+}
 
-            self.print_visible_folders_list(stdout)?;
+/// Writes the entries of `lister` as NDJSON while the directory is read
+///
+/// They come in the order the file system yields them, so that huge directories
+/// start printing at once and are never held in memory. The summary comes last
+///
+/// # Errors
+/// Will return an error if the directory cannot be opened or `out` cannot be written
+pub fn stream_ndjson<W: Write>(
+    out: &mut W,
+    lister: &Lister,
+    options: &DisplayOptions,
+) -> Result<Vec<Error>> {
+    let users = UsersCache::new();
+    let names = options.writes(Field::User) || options.writes(Field::Group);
+    let mut summary = Summary::default();
+
+    let errors = lister.for_each(|entry, git| {
+        summary.add(entry);
+
+        let record = Record::from(entry, git, names.then_some(&users));
+
+        match write_ndjson(out, &record, &options.fields) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::Write(e)),
         }
+    })?;
+
+    if options.summary {
+        match write_ndjson_summary(out, summary) {
+            Ok(()) => {}
+            Err(e) => return Err(Error::Write(e)),
+        }
+    }
 
-        if !self.hidden_files.is_empty() || !self.files.is_empty() {
-            if all {
-                self.print_hidden_files_list(stdout)?;
+    Ok(errors)
+}
+
+impl Directory {
+    /// Reads the directory, entries that cannot be read are kept by name
+    /// and the reasons collected in `errors`
+    ///
+    /// # Errors
+    /// Will return an error if the directory cannot be opened
+    pub fn from(root: &Path, hidden: Hidden, filter: &Filter) -> Result<Self> {
+        let mut errors = Vec::new();
+
+        let dots = if hidden == Hidden::All {
+            let mut dots = vec![read_entry(
+                root.to_path_buf(),
+                OsString::from("."),
+                true,
+                &mut errors,
+            )];
+
+            if let Some(parent) = root.parent() {
+                dots.push(read_entry(
+                    parent.to_path_buf(),
+                    OsString::from(".."),
+                    true,
+                    &mut errors,
+                ));
             }
 
-            self.print_visible_files_list(stdout)?;
-        }
+            dots
+        } else {
+            Vec::new()
+        };
 
-        Ok(())
+        let mut entries = Vec::new();
+
+        read_each(root, hidden, filter, &mut errors, |entry| {
+            entries.push(entry);
+            Ok(())
+        })?;
+
+        entries
+            .sort_by(|a, b| (!a.folder, !a.hidden, &a.name).cmp(&(!b.folder, !b.hidden, &b.name)));
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            dots,
+            entries,
+            git: None,
+            errors,
+        })
     }
 
-    fn print_hidden_folders(
+    /// The entries shown in the text formats, hidden ones only with `all`
+    fn shown<'a>(&'a self, options: &DisplayOptions) -> impl Iterator<Item = &'a Entry> {
+        let all = options.all;
+
+        self.entries
+            .iter()
+            .filter(move |entry| all || !entry.hidden)
+    }
+
+    fn status(&self, name: &OsStr) -> Option<GitStatus> {
+        self.git.as_ref().map(|statuses| statuses.get(name))
+    }
+
+    /// Writes the listing to `out`, laying the grid out in the columns given by `width`
+    ///
+    /// # Errors
+    /// Will return an error if it cannot write to `out`
+    pub fn display_output<W: Write>(
         &self,
-        stdout: &mut StdoutLock,
-        count: &mut usize,
-        width: usize,
+        out: &mut W,
+        width: &impl Width,
+        options: &DisplayOptions,
     ) -> Result<()> {
-        for file in &self.hidden_folders {
-            if width - *count < self.max_space() + 4 {
-                match writeln!(stdout) {
-                    Ok(()) => {}
-                    Err(_) => return Err(Error::from("Cannot display output in stdout")),
-                }
-                *count = 0;
+        match options.format {
+            Format::Text if options.list => self.print_list(out, options)?,
+            Format::Text => self.print_nlist(out, width.width(), options)?,
+            Format::Json | Format::Ndjson | Format::Csv | Format::Tsv => {
+                return self.print_records(out, options);
             }
+        }
 
-            match write!(
-                stdout,
-                "\x1B[1;92m\u{ea83} \x1B[0 \x1B[1;96m{}{}\x1B[0",
-                file.name,
-                " ".repeat(self.max_space() - file.name.len())
-            ) {
+        if options.summary {
+            match writeln!(out, "\n{}", self.summary()) {
                 Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot display output in stdout")),
+                Err(e) => return Err(Error::Write(e)),
             }
-
-            *count += self.max_space() + 4;
         }
 
         Ok(())
     }
 
-    fn print_visible_folders(
-        &self,
-        stdout: &mut StdoutLock,
-        count: &mut usize,
-        width: usize,
-    ) -> Result<()> {
-        for file in &self.folders {
-            if width - *count < self.max_space() + 4 {
-                match writeln!(stdout) {
-                    Ok(()) => {}
-                    Err(_) => return Err(Error::from("Cannot display output in stdout")),
-                }
-                *count = 0;
-            }
+    /// Counts the entries and adds up the size of the files
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        self.entries.iter().for_each(|entry| summary.add(entry));
 
-            match write!(
-                stdout,
-                "\x1B[92m\u{ea83} \x1B[0 \x1B[32m{}{}\x1B[0",
-                file.name,
-                " ".repeat(self.max_space() - file.name.len())
-            ) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot display output in stdout")),
-            }
+        summary
+    }
 
-            *count += self.max_space() + 4;
-        }
+    /// Every entry with its raw metadata, in the order they are displayed, the
+    /// ones whose metadata cannot be read by name only. Owner and group names
+    /// are only looked up with `users`
+    pub fn records<'a>(
+        &'a self,
+        users: Option<&'a UsersCache>,
+    ) -> impl Iterator<Item = Record> + 'a {
+        self.entries
+            .iter()
+            .map(move |entry| Record::from(entry, self.status(&entry.name), users))
+    }
 
-        Ok(())
+    fn print_records<W: Write>(&self, out: &mut W, options: &DisplayOptions) -> Result<()> {
+        let users = UsersCache::new();
+        let names = options.writes(Field::User) || options.writes(Field::Group);
+
+        match write_records(
+            out,
+            options.format,
+            &self.root,
+            self.records(names.then_some(&users)),
+            &options.fields,
+            options.summary.then(|| self.summary()),
+        ) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(Error::Write(e)),
+        }
     }
 
-    fn print_hidden_files(
+    fn print_nlist<W: Write>(
         &self,
-        stdout: &mut StdoutLock,
-        count: &mut usize,
+        out: &mut W,
         width: usize,
+        options: &DisplayOptions,
     ) -> Result<()> {
-        for file in &self.hidden_files {
-            if width - *count < self.max_space() + 4 {
-                match writeln!(stdout) {
+        let cells = self.grid_cells(options);
+
+        let layout = Layout::fit(
+            &cells.iter().map(|cell| cell.width).collect::<Vec<_>>(),
+            width,
+            options.direction,
+        );
+
+        for row in 0..layout.rows {
+            for column in 0..layout.widths.len() {
+                let Some(index) = layout.cell(row, column) else {
+                    break;
+                };
+
+                let cell = &cells[index];
+
+                match write!(
+                    out,
+                    "{}{}",
+                    cell.text,
+                    " ".repeat(layout.padding(row, column, cell.width))
+                ) {
                     Ok(()) => {}
-                    Err(_) => return Err(Error::from("Cannot display output in stdout")),
+                    Err(e) => return Err(Error::Write(e)),
                 }
-                *count = 0;
             }
 
-            match write!(
-                stdout,
-                "\x1B[94m\u{ea7b} \x1B[0 \x1B[96m{}{}\x1B[0",
-                file.name,
-                " ".repeat(self.max_space() - file.name.len())
-            ) {
+            match writeln!(out) {
                 Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot display output in stdout")),
+                Err(e) => return Err(Error::Write(e)),
             }
-
-            *count += self.max_space() + 4;
         }
 
         Ok(())
     }
 
-    fn print_visible_files(
-        &self,
-        stdout: &mut StdoutLock,
-        count: &mut usize,
-        width: usize,
-    ) -> Result<()> {
-        for file in &self.files {
-            if width - *count < self.max_space() + 4 {
-                match writeln!(stdout) {
-                    Ok(()) => {}
-                    Err(_) => return Err(Error::from("Cannot display output in stdout")),
-                }
-                *count = 0;
-            }
+    fn print_list<W: Write>(&self, out: &mut W, options: &DisplayOptions) -> Result<()> {
+        let columns = self.list_columns(options);
+
+        let lines = self.list_lines(options);
+        let users = UsersCache::new();
+
+        let rows = lines
+            .iter()
+            .map(|line| {
+                columns
+                    .iter()
+                    .map(|column| line.cell(*column, options, &users))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let widths = columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| {
+                rows.iter()
+                    .map(|row| row[index].width)
+                    .chain(options.header.then(|| column.header().len()))
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        if options.header {
+            let header = columns
+                .iter()
+                .map(|column| Cell {
+                    text: format!("\x1B[4m{}\x1B[0m", column.header()),
+                    width: column.header().len(),
+                })
+                .collect::<Vec<_>>();
+
+            Self::print_list_row(out, &columns, &widths, &header)?;
+        }
 
-            match write!(
-                stdout,
-                "\x1B[94m\u{ea7b} \x1B[0 \x1B[0m{}{}\x1B[0",
-                file.name,
-                " ".repeat(self.max_space() - file.name.len())
-            ) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot display output in stdout")),
-            }
+        for (line, row) in lines.iter().zip(&rows) {
+            Self::print_list_row(out, &columns, &widths, row)?;
 
-            *count += self.max_space() + 4;
+            if options.xattr {
+                for name in &line.entry.xattrs {
+                    match writeln!(out, "\t{}", name.to_string_lossy()) {
+                        Ok(()) => {}
+                        Err(e) => return Err(Error::Write(e)),
+                    }
+                }
+            }
         }
 
         Ok(())
     }
 
-    fn print_hidden_folders_list(&self, stdout: &mut StdoutLock) -> Result<()> {
-        let Some(cur_dir) = self.cur_dir.as_ref() else {
-            return Err(Error::from("Cannot reference current directory object"));
-        };
+    /// The columns picked in the options, with the security context, the type, the
+    /// line count and the git status before the name when they were asked for but not picked
+    fn list_columns(&self, options: &DisplayOptions) -> Vec<Column> {
+        let mut columns = options.columns.clone();
 
-        if cur_dir.name != "-" {
-            let Some(parent_dir) = self.parent_dir.as_ref() else {
-                return Err(Error::from("Cannot reference parent directory object"));
-            };
+        if options.device && !columns.contains(&Column::Device) {
+            columns.insert(0, Column::Device);
+        }
 
-            Self::print_list_folder(parent_dir, stdout)?;
+        if options.inode && !columns.contains(&Column::Inode) {
+            columns.insert(0, Column::Inode);
         }
 
-        Self::print_list_folder(cur_dir, stdout)?;
+        let name = |columns: &[Column]| {
+            columns
+                .iter()
+                .position(|column| *column == Column::Name)
+                .unwrap_or(columns.len())
+        };
 
-        for file in &self.hidden_folders {
-            Self::print_list_folder(file, stdout)?;
+        if options.context && !columns.contains(&Column::Context) {
+            columns.insert(name(&columns), Column::Context);
         }
 
-        Ok(())
-    }
-
-    fn print_visible_folders_list(&self, stdout: &mut StdoutLock) -> Result<()> {
-        for file in &self.folders {
-            match Self::print_list_folder(file, stdout) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot write to stdout")),
-            }
+        if options.mime && !columns.contains(&Column::Mime) {
+            columns.insert(name(&columns), Column::Mime);
         }
 
-        Ok(())
-    }
+        if options.lines && !columns.contains(&Column::Lines) {
+            columns.insert(name(&columns), Column::Lines);
+        }
 
-    fn print_hidden_files_list(&self, stdout: &mut StdoutLock) -> Result<()> {
-        for file in &self.hidden_files {
-            match Self::print_list_file(file, stdout) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot write to stdout")),
-            }
+        if self.git.is_some() && !columns.contains(&Column::Git) {
+            columns.insert(name(&columns), Column::Git);
         }
 
-        Ok(())
+        columns
     }
 
-    fn print_visible_files_list(&self, stdout: &mut StdoutLock) -> Result<()> {
-        for file in &self.files {
-            match Self::print_list_file(file, stdout) {
-                Ok(()) => {}
-                Err(_) => return Err(Error::from("Cannot write ro stdout")),
-            }
-        }
-
-        Ok(())
+    fn list_lines(&self, options: &DisplayOptions) -> Vec<Line<'_>> {
+        let dots = self.dots.iter().filter(|_| options.all).map(|entry| Line {
+            entry,
+            status: None,
+        });
+
+        dots.chain(self.shown(options).map(|entry| Line {
+            entry,
+            status: self.status(&entry.name),
+        }))
+        .collect()
     }
 
-    fn print_list_file(file: &File, stdout: &mut StdoutLock) -> Result<()> {
-        match writeln!(
-            stdout,
-            "\x1B[0m{: <10} {: <4}{: <6} {} \x1B[0 \x1B[94m\u{ea7b} \x1B[0 \x1B[34m{: <25} \x1B[0",
-            file.permissions(),
-            1,
-            file.formatted_size(),
-            file.created_time(),
-            file.name
-        ) {
+    fn print_list_row<W: Write>(
+        out: &mut W,
+        columns: &[Column],
+        widths: &[usize],
+        cells: &[Cell],
+    ) -> Result<()> {
+        let last = columns.len().saturating_sub(1);
+
+        let line = columns
+            .iter()
+            .zip(widths)
+            .zip(cells)
+            .enumerate()
+            .map(|(index, ((column, width), cell))| {
+                let padding = " ".repeat(width.saturating_sub(cell.width));
+
+                if column.right_aligned() {
+                    format!("{padding}{}", cell.text)
+                } else if index == last {
+                    cell.text.clone()
+                } else {
+                    format!("{}{padding}", cell.text)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        match writeln!(out, "{line}") {
             Ok(()) => Ok(()),
-            Err(_) => Err(Error::from("Cannot write to stdout")),
+            Err(e) => Err(Error::Write(e)),
         }
     }
 
-    fn print_list_folder(file: &Folder, stdout: &mut StdoutLock) -> Result<()> {
-        match writeln!(
-            stdout,
-            "\x1B[0m{: <10} {: <4}{: <6} {} \x1B[0 \x1B[92m\u{ea83} \x1B[0 \x1B[1;32m{: <25} \x1B[0",
-            file.permissions(),
-            file.children(),
-            '-',
-            file.created_time(),
-            file.name
-        ) {
-            Ok(()) => Ok(()),
-            Err(_) => Err(Error::from("Cannot write to stdout")),
+    fn grid_cells(&self, options: &DisplayOptions) -> Vec<Cell> {
+        let cells = self.name_cells(options);
+
+        if !options.inode && !options.device && !options.context {
+            return cells.into_iter().map(|(_, cell)| cell).collect();
         }
+
+        let identities = cells
+            .iter()
+            .map(|(entry, _)| {
+                let field = |format: fn(&Stat) -> String| {
+                    entry
+                        .stat
+                        .as_ref()
+                        .map_or_else(|| String::from("?"), format)
+                };
+
+                let mut identity = Vec::new();
+
+                if options.inode {
+                    identity.push(field(|stat| stat.inode.to_string()));
+                }
+
+                if options.device {
+                    identity.push(field(|stat| format_device(stat.device)));
+                }
+
+                if options.context {
+                    identity.push(entry.context.clone().unwrap_or_else(|| String::from("?")));
+                }
+
+                identity.join(" ")
+            })
+            .collect::<Vec<_>>();
+
+        let width = identities.iter().map(String::len).max().unwrap_or_default();
+
+        cells
+            .into_iter()
+            .zip(identities)
+            .map(|((_, cell), identity)| Cell {
+                text: format!("{identity:>width$} {}", cell.text),
+                width: width + 1 + cell.width,
+            })
+            .collect()
+    }
+
+    /// Every entry in the grid, with its styled cell
+    fn name_cells(&self, options: &DisplayOptions) -> Vec<(&Entry, Cell)> {
+        self.shown(options)
+            .map(|entry| {
+                let content = entry.mime.and_then(Mime::color);
+
+                let (icon_color, color) = match (entry.folder, entry.hidden, content) {
+                    _ if entry.locked => (LOCKED, LOCKED),
+                    _ if entry.is_executable() => ("94", EXECUTABLE),
+                    (false, _, Some(color)) => ("94", color),
+                    (true, true, _) => ("1;92", "1;96"),
+                    (true, false, _) => ("92", "32"),
+                    (false, true, None) => ("94", "96"),
+                    (false, false, None) => ("94", "0"),
+                };
+
+                let cell = Cell::new(entry, options, icon_color, color, self.status(&entry.name));
+
+                (entry, cell)
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{collections::BTreeSet, path::PathBuf};
+    use std::{
+        ffi::{OsStr, OsString},
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+    };
+
+    use uzers::UsersCache;
+
+    use crate::{
+        column::Column,
+        dir::{read_entry, stream_ndjson, Directory, DisplayOptions, Hidden},
+        entry::{Entry, Indicator, Kind, Stat},
+        filter::Filter,
+        glob::Pattern,
+        grid::Direction,
+        icons::IconSet,
+        lines,
+        output::{Field, Format},
+        quote::QuotingStyle,
+        support::format_permissions,
+        Error, Lister,
+    };
+
+    fn options(icons: Option<IconSet>, quoting: QuotingStyle) -> DisplayOptions {
+        DisplayOptions {
+            all: false,
+            list: false,
+            icons,
+            direction: Direction::Down,
+            quoting,
+            format: Format::Text,
+            fields: Vec::new(),
+            columns: Column::DEFAULT.to_vec(),
+            header: false,
+            inode: false,
+            device: false,
+            xattr: false,
+            context: false,
+            indicator: Indicator::None,
+            mime: false,
+            lines: false,
+            summary: false,
+        }
+    }
 
-    use crate::dir::Directory;
+    /// Folders, hidden folders, files and hidden files
+    fn counts(directory: &Directory) -> (usize, usize, usize, usize) {
+        let count = |folder: bool, hidden: bool| {
+            directory
+                .entries
+                .iter()
+                .filter(|entry| entry.folder == folder && entry.hidden == hidden)
+                .count()
+        };
+
+        (
+            count(true, false),
+            count(true, true),
+            count(false, false),
+            count(false, true),
+        )
+    }
 
     fn create_temp_directory_structure() -> PathBuf {
         let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
@@ -379,39 +835,738 @@ mod tests {
         let file2 = dir1.join("file2.txt");
         std::fs::File::create(file2).expect("Failed to create file");
 
-        root_dir.into_path()
+        root_dir.keep()
     }
 
     #[test]
     fn test_from() {
         let root_dir = create_temp_directory_structure();
 
-        let directory =
-            Directory::from(&root_dir, false, true).expect("Failed to create Directory");
+        let directory = Directory::from(&root_dir, Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
 
-        assert!(directory.cur_dir.is_none());
-        assert!(directory.parent_dir.is_none());
-        assert_eq!(directory.folders.len(), 2);
-        assert_eq!(directory.hidden_folders.len(), 0);
-        assert_eq!(directory.files.len(), 0);
-        assert_eq!(directory.hidden_files.len(), 0);
-        assert_eq!(directory.largest_name, 4);
+        assert!(directory.dots.is_empty());
+        assert_eq!(counts(&directory), (2, 0, 0, 0));
 
         std::fs::remove_dir_all(&root_dir).expect("Failed to remove temporary directory");
     }
 
     #[test]
-    fn test_max_space() {
+    fn test_grid_cells_wide_names() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::create_dir(root_dir.path().join("\u{6587}\u{4ef6}\u{5939}"))
+            .expect("Failed to create directory");
+        std::fs::File::create(root_dir.path().join("cafe\u{301}.txt"))
+            .expect("Failed to create file");
+
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+
+        let widths = |icons| {
+            directory
+                .grid_cells(&options(icons, QuotingStyle::Literal))
+                .iter()
+                .map(|cell| cell.width)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(widths(None), vec![6, 8]);
+        assert_eq!(widths(Some(IconSet::Ascii)), vec![8, 10]);
+    }
+
+    #[test]
+    fn test_from_invalid_utf8_names() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::File::create(root_dir.path().join(OsStr::from_bytes(b"a\xff")))
+            .expect("Failed to create file");
+        std::fs::File::create(root_dir.path().join(OsStr::from_bytes(b"a\xfe")))
+            .expect("Failed to create file");
+
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+
+        assert_eq!(counts(&directory), (0, 0, 2, 0));
+
+        let cells = directory.grid_cells(&options(None, QuotingStyle::ShellEscape));
+
+        assert!(cells[0].text.contains("'a'$'\\376'"));
+        assert!(cells[1].text.contains("'a'$'\\377'"));
+    }
+
+    #[test]
+    fn test_from_filter() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        for name in ["a.log", "b.log", "b.log~", "notes.txt"] {
+            std::fs::File::create(root_dir.path().join(name)).expect("Failed to create file");
+        }
+        std::fs::create_dir(root_dir.path().join("target")).expect("Failed to create directory");
+
+        let filter = Filter {
+            ignore: vec![Pattern::new("a.*")],
+            only: vec![Pattern::new("*.log*")],
+            ignore_backups: true,
+            ..Filter::default()
+        };
+
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &filter)
+            .expect("Failed to create Directory");
+
+        assert_eq!(counts(&directory), (0, 0, 1, 0));
+        assert_eq!(directory.entries[0].name, "b.log");
+    }
+
+    #[test]
+    fn test_from_almost_all() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        for name in [".env", "main.py", ".main.py.swp"] {
+            std::fs::File::create(root_dir.path().join(name)).expect("Failed to create file");
+        }
+        std::fs::create_dir(root_dir.path().join("__pycache__"))
+            .expect("Failed to create directory");
+
+        let filter = Filter {
+            hidden: vec![Pattern::new("__pycache__"), Pattern::new("*.swp")],
+            ..Filter::default()
+        };
+
+        let directory = Directory::from(root_dir.path(), Hidden::AlmostAll, &filter)
+            .expect("Failed to create Directory");
+
+        assert!(directory.dots.is_empty());
+        assert_eq!(counts(&directory), (0, 1, 1, 2));
+
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &filter)
+            .expect("Failed to create Directory");
+
+        assert_eq!(counts(&directory), (0, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_records() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::write(root_dir.path().join("b.txt"), b"abc").expect("Failed to write file");
+        std::fs::write(root_dir.path().join(".env"), b"").expect("Failed to write file");
+        std::fs::create_dir(root_dir.path().join("a")).expect("Failed to create directory");
+
+        let directory = Directory::from(root_dir.path(), Hidden::AlmostAll, &Filter::default())
+            .expect("Failed to create Directory");
+
+        let records = directory.records(None).collect::<Vec<_>>();
+        let names = records
+            .iter()
+            .map(|record| (record.name.as_str(), record.hidden))
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, vec![("a", false), (".env", true), ("b.txt", false)]);
+        assert_eq!(records[2].size, Some(3));
+        assert_eq!(records[2].user, None);
+
+        let users = UsersCache::new();
+        let records = directory.records(Some(&users)).collect::<Vec<_>>();
+
+        assert_eq!(
+            records[2].user,
+            uzers::get_user_by_uid(uzers::get_effective_uid())
+                .map(|user| user.name().to_string_lossy().into_owned())
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::write(root_dir.path().join("a.bin"), vec![0; 1000]).expect("Failed to write");
+        std::fs::write(root_dir.path().join(".b"), vec![0; 24]).expect("Failed to write");
+        std::fs::create_dir(root_dir.path().join("src")).expect("Failed to create directory");
+        std::fs::create_dir(root_dir.path().join(".git")).expect("Failed to create directory");
+
+        let directory = Directory::from(root_dir.path(), Hidden::AlmostAll, &Filter::default())
+            .expect("Failed to create Directory");
+
+        assert_eq!(
+            directory.summary().to_string(),
+            "2 folders, 2 files (2 hidden), 1.0 KiB total"
+        );
+
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+
+        assert_eq!(
+            directory.summary().to_string(),
+            "1 folder, 1 file, 1000 B total"
+        );
+    }
+
+    #[test]
+    fn test_stream_ndjson() {
+        let root_dir = golden_fixture();
+        let lister = Lister::new(root_dir.path()).hidden(Hidden::AlmostAll);
+
+        let mut out = Vec::new();
+        let errors = stream_ndjson(
+            &mut out,
+            &lister,
+            &DisplayOptions {
+                format: Format::Ndjson,
+                fields: vec![Field::Name, Field::Hidden],
+                summary: true,
+                ..options(None, QuotingStyle::Literal)
+            },
+        )
+        .expect("Failed to stream");
+
+        let out = String::from_utf8(out).expect("Streamed invalid UTF-8");
+        let mut lines = out.lines().collect::<Vec<_>>();
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            lines.pop(),
+            Some("{\"summary\":{\"folders\":1,\"files\":5,\"hidden\":1,\"size\":4326}}")
+        );
+
+        lines.sort_unstable();
+
+        assert_eq!(
+            lines,
+            [
+                "{\"name\":\".env\",\"hidden\":true}",
+                "{\"name\":\"a.txt\",\"hidden\":false}",
+                "{\"name\":\"bb.txt\",\"hidden\":false}",
+                "{\"name\":\"ccc.txt\",\"hidden\":false}",
+                "{\"name\":\"dddd.txt\",\"hidden\":false}",
+                "{\"name\":\"src\",\"hidden\":false}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_from_errors() {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+
+        let missing = Directory::from(
+            &root_dir.path().join("missing"),
+            Hidden::Skip,
+            &Filter::default(),
+        );
+
+        assert!(matches!(missing, Err(Error::OpenDirectory { .. })));
+
+        let mut errors = Vec::new();
+        let entry = read_entry(
+            root_dir.path().join("gone"),
+            OsString::from("gone"),
+            false,
+            &mut errors,
+        );
+
+        assert_eq!(entry.name, "gone");
+        assert_eq!(entry.stat, None);
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![format!(
+                "cannot access '{}': No such file or directory",
+                root_dir.path().join("gone").display()
+            )]
+        );
+
+        let directory = Directory::from(root_dir.path(), Hidden::All, &Filter::default())
+            .expect("Failed to create Directory");
+
+        assert!(directory.errors.is_empty());
+    }
+
+    /// A directory whose listing only depends on the names and sizes of its entries
+    fn golden_fixture() -> tempfile::TempDir {
+        let root_dir = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::create_dir(root_dir.path().join("src")).expect("Failed to create directory");
+        for (name, size) in [
+            ("a.txt", 1),
+            ("bb.txt", 20),
+            ("ccc.txt", 300),
+            ("dddd.txt", 4000),
+        ] {
+            std::fs::write(root_dir.path().join(name), vec![0; size]).expect("Failed to write");
+        }
+        std::fs::write(root_dir.path().join(".env"), b"KEY=1").expect("Failed to write");
+
+        root_dir
+    }
+
+    fn render(root: &Path, hidden: Hidden, width: usize, options: &DisplayOptions) -> String {
+        let directory =
+            Directory::from(root, hidden, &Filter::default()).expect("Failed to create Directory");
+
+        let mut out = Vec::new();
+        directory
+            .display_output(&mut out, &width, options)
+            .expect("Failed to render");
+
+        String::from_utf8(out).expect("Rendered invalid UTF-8")
+    }
+
+    /// Drops the colors so the expected output stays readable
+    fn plain(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+
+        while let Some(c) = chars.next() {
+            if c == '\x1B' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+
+        plain
+    }
+
+    #[test]
+    fn test_lists_and_writes() {
+        let list = DisplayOptions {
+            list: true,
+            context: true,
+            columns: vec![Column::Perms, Column::Name],
+            ..options(None, QuotingStyle::Literal)
+        };
+
+        assert!(list.lists(Column::Perms));
+        assert!(list.lists(Column::Context));
+        assert!(!list.lists(Column::Size));
+        assert!(!list.writes(Field::Xattrs));
+
+        let grid = options(None, QuotingStyle::Literal);
+
+        assert!(!grid.lists(Column::Perms));
+
+        let json = DisplayOptions {
+            format: Format::Json,
+            fields: vec![Field::Name],
+            ..options(None, QuotingStyle::Literal)
+        };
+
+        assert!(json.writes(Field::Name));
+        assert!(!json.writes(Field::Xattrs));
+        assert!(DisplayOptions {
+            fields: Vec::new(),
+            ..json
+        }
+        .writes(Field::Xattrs));
+    }
+
+    #[test]
+    fn test_render_grid() {
+        let root_dir = golden_fixture();
+
+        let down = render(
+            root_dir.path(),
+            Hidden::Skip,
+            30,
+            &options(None, QuotingStyle::Literal),
+        );
+
+        assert_eq!(
+            down,
+            "\x1B[92m\x1B[0m\x1B[32msrc\x1B[0m    \x1B[94m\x1B[0m\x1B[0mbb.txt\x1B[0m   \
+             \x1B[94m\x1B[0m\x1B[0mdddd.txt\x1B[0m\n\
+             \x1B[94m\x1B[0m\x1B[0ma.txt\x1B[0m  \x1B[94m\x1B[0m\x1B[0mccc.txt\x1B[0m\n"
+        );
+
+        let across = render(
+            root_dir.path(),
+            Hidden::Skip,
+            30,
+            &DisplayOptions {
+                direction: Direction::Across,
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            plain(&across),
+            "src      a.txt     bb.txt\nccc.txt  dddd.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_render_indicators() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root_dir = golden_fixture();
+        let script = root_dir.path().join("bb.txt");
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))
+            .expect("Failed to set permissions");
+        std::os::unix::fs::symlink("a.txt", root_dir.path().join("link")).expect("Failed to link");
+
+        let classify = render(
+            root_dir.path(),
+            Hidden::Skip,
+            80,
+            &DisplayOptions {
+                indicator: Indicator::Classify,
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert!(classify.contains("\x1B[1;33mbb.txt\x1B[0m*"));
+        assert_eq!(
+            plain(&classify),
+            "src/  a.txt  bb.txt*  ccc.txt  dddd.txt  link@\n"
+        );
+
+        let slash = render(
+            root_dir.path(),
+            Hidden::Skip,
+            80,
+            &DisplayOptions {
+                indicator: Indicator::Slash,
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            plain(&slash),
+            "src/  a.txt  bb.txt  ccc.txt  dddd.txt  link\n"
+        );
+    }
+
+    #[test]
+    fn test_render_mime() {
+        let root_dir = golden_fixture();
+        std::fs::write(root_dir.path().join("a.txt"), b"\x89PNG\r\n\x1A\n")
+            .expect("Failed to write");
+        std::fs::write(root_dir.path().join("bb.txt"), b"#!/bin/sh\n").expect("Failed to write");
+
+        let mut directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+        directory.entries.iter_mut().for_each(Entry::sniff);
+
+        let mut out = Vec::new();
+        directory
+            .display_output(
+                &mut out,
+                &80,
+                &DisplayOptions {
+                    list: true,
+                    mime: true,
+                    columns: vec![Column::Name],
+                    ..options(Some(IconSet::Nerd), QuotingStyle::Literal)
+                },
+            )
+            .expect("Failed to render");
+        let output = String::from_utf8(out).expect("Rendered invalid UTF-8");
+
+        assert!(output.contains("\x1B[94m\u{f1c5} \x1B[0m\x1B[35ma.txt"));
+        assert_eq!(
+            plain(&output).lines().collect::<Vec<_>>(),
+            vec![
+                "-                        \u{f19fc} src",
+                "image/png                \u{f1c5} a.txt",
+                "text/x-script            \u{f489} bb.txt",
+                "application/octet-stream \u{f15c} ccc.txt",
+                "application/octet-stream \u{f15c} dddd.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_lines() {
+        let root_dir = golden_fixture();
+        std::fs::write(root_dir.path().join("bb.txt"), b"one\ntwo\nthree\n")
+            .expect("Failed to write");
+
+        let mut directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+        lines::count_all(&mut directory.entries, 1024);
+
+        let mut out = Vec::new();
+        directory
+            .display_output(
+                &mut out,
+                &80,
+                &DisplayOptions {
+                    list: true,
+                    lines: true,
+                    header: true,
+                    columns: vec![Column::Name],
+                    ..options(None, QuotingStyle::Literal)
+                },
+            )
+            .expect("Failed to render");
+
+        assert_eq!(
+            plain(&String::from_utf8(out).expect("Rendered invalid UTF-8")),
+            "Lines Name\n    - src\n    - a.txt\n    3 bb.txt\n    - ccc.txt\n    - dddd.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_render_narrow_with_summary() {
+        let root_dir = golden_fixture();
+
+        let output = render(
+            root_dir.path(),
+            Hidden::AlmostAll,
+            10,
+            &DisplayOptions {
+                all: true,
+                summary: true,
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            plain(&output),
+            "src\n.env\na.txt\nbb.txt\nccc.txt\ndddd.txt\n\n\
+             1 folder, 5 files (1 hidden), 4.2 KiB total\n"
+        );
+    }
+
+    #[test]
+    fn test_render_list() {
+        let root_dir = golden_fixture();
+
+        let output = render(
+            root_dir.path(),
+            Hidden::Skip,
+            80,
+            &DisplayOptions {
+                list: true,
+                header: true,
+                columns: vec![Column::Size, Column::Name],
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            plain(&output),
+            "Size Name\n   - src\n  1b a.txt\n 20b bb.txt\n300b ccc.txt\n  3k dddd.txt\n"
+        );
+    }
+
+    #[test]
+    fn test_render_owner() {
+        let root_dir = golden_fixture();
+
+        let output = render(
+            root_dir.path(),
+            Hidden::Skip,
+            80,
+            &DisplayOptions {
+                list: true,
+                columns: vec![Column::Owner, Column::Group, Column::Name],
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        let owner = uzers::get_user_by_uid(uzers::get_effective_uid()).map_or_else(
+            || uzers::get_effective_uid().to_string(),
+            |user| user.name().to_string_lossy().into_owned(),
+        );
+        let output = plain(&output);
+
+        assert_eq!(output.lines().count(), 5);
+        assert!(output
+            .lines()
+            .all(|line| line.split_whitespace().next() == Some(owner.as_str())));
+    }
+
+    #[test]
+    fn test_render_records() {
+        let root_dir = golden_fixture();
+
+        let csv = render(
+            root_dir.path(),
+            Hidden::Skip,
+            80,
+            &DisplayOptions {
+                format: Format::Csv,
+                fields: vec![Field::Name, Field::Kind],
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            csv,
+            "name,kind\nsrc,directory\na.txt,file\nbb.txt,file\nccc.txt,file\ndddd.txt,file\n"
+        );
+
+        let ndjson = render(
+            root_dir.path(),
+            Hidden::AlmostAll,
+            80,
+            &DisplayOptions {
+                format: Format::Ndjson,
+                fields: vec![Field::Name, Field::Hidden],
+                ..options(None, QuotingStyle::Literal)
+            },
+        );
+
+        assert_eq!(
+            ndjson,
+            "{\"name\":\"src\",\"hidden\":false}\n{\"name\":\".env\",\"hidden\":true}\n\
+             {\"name\":\"a.txt\",\"hidden\":false}\n{\"name\":\"bb.txt\",\"hidden\":false}\n\
+             {\"name\":\"ccc.txt\",\"hidden\":false}\n{\"name\":\"dddd.txt\",\"hidden\":false}\n"
+        );
+    }
+
+    #[test]
+    fn test_render_locked() {
+        let stat = Stat {
+            mode: 0o040_700,
+            size: 4096,
+            links: 2,
+            inode: 1,
+            device: 0,
+            uid: 0,
+            gid: 0,
+            modified: None,
+            accessed: None,
+            created: None,
+        };
+        let locked = Entry {
+            name: OsString::from("private"),
+            path: PathBuf::from("/srv/private"),
+            kind: Some(Kind::Directory),
+            hidden: false,
+            folder: true,
+            locked: true,
+            stat: Some(stat),
+            link_target: None,
+            xattrs: Vec::new(),
+            context: None,
+            mime: None,
+            lines: None,
+        };
+        let unreadable = Entry::unreadable(
+            PathBuf::from("/srv/secret"),
+            OsString::from("secret"),
+            false,
+        );
+
         let directory = Directory {
-            cur_dir: None,
-            parent_dir: None,
-            folders: BTreeSet::new(),
-            hidden_folders: BTreeSet::new(),
-            files: BTreeSet::new(),
-            hidden_files: BTreeSet::new(),
-            largest_name: 10,
+            root: PathBuf::from("/srv"),
+            dots: Vec::new(),
+            entries: vec![locked, unreadable],
+            git: None,
+            errors: Vec::new(),
         };
 
-        assert_eq!(directory.max_space(), 14);
+        let render = |options: &DisplayOptions| {
+            let mut out = Vec::new();
+            directory
+                .display_output(&mut out, &80, options)
+                .expect("Failed to render");
+
+            String::from_utf8(out).expect("Rendered invalid UTF-8")
+        };
+
+        let grid = render(&options(Some(IconSet::Ascii), QuotingStyle::Literal));
+
+        assert!(grid.starts_with("\x1B[31m! \x1B[0m\x1B[31mprivate\x1B[0m"));
+        assert_eq!(plain(&grid), "! private  - secret\n");
+
+        let csv = render(&DisplayOptions {
+            format: Format::Csv,
+            fields: vec![Field::Name, Field::Kind, Field::Size],
+            ..options(None, QuotingStyle::Literal)
+        });
+
+        assert_eq!(csv, "name,kind,size\nprivate,directory,4096\nsecret,,\n");
+
+        let list = render(&DisplayOptions {
+            list: true,
+            columns: vec![Column::Perms, Column::Links, Column::Size, Column::Name],
+            ..options(Some(IconSet::Ascii), QuotingStyle::Literal)
+        });
+
+        assert_eq!(
+            plain(&list),
+            "drwx------ 2 ? ! private\n?????????? ? ? - secret\n"
+        );
+    }
+
+    #[test]
+    fn test_render_xattrs() {
+        let root_dir = golden_fixture();
+        let mut directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+        directory.entries.retain(|entry| entry.name == "a.txt");
+
+        let entry = &mut directory.entries[0];
+        entry.xattrs = vec![
+            OsString::from("security.selinux"),
+            OsString::from("user.origin"),
+        ];
+        entry.context = Some(String::from("system_u:object_r:tmp_t:s0"));
+        let mode = entry.stat.map(|stat| format_permissions(stat.mode));
+
+        let render = |options: &DisplayOptions| {
+            let mut out = Vec::new();
+            directory
+                .display_output(&mut out, &80, options)
+                .expect("Failed to render");
+
+            plain(&String::from_utf8(out).expect("Rendered invalid UTF-8"))
+        };
+
+        let list = render(&DisplayOptions {
+            list: true,
+            xattr: true,
+            context: true,
+            columns: vec![Column::Perms, Column::Name],
+            ..options(None, QuotingStyle::Literal)
+        });
+
+        assert_eq!(
+            list,
+            format!(
+                "{}@ system_u:object_r:tmp_t:s0 a.txt\n\tsecurity.selinux\n\tuser.origin\n",
+                mode.unwrap_or_default()
+            )
+        );
+
+        let grid = render(&DisplayOptions {
+            context: true,
+            ..options(None, QuotingStyle::Literal)
+        });
+
+        assert_eq!(grid, "system_u:object_r:tmp_t:s0 a.txt\n");
+    }
+
+    /// A reader that went away after the first line
+    struct ClosedPipe;
+
+    impl std::io::Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_render_broken_pipe() {
+        let root_dir = golden_fixture();
+        let directory = Directory::from(root_dir.path(), Hidden::Skip, &Filter::default())
+            .expect("Failed to create Directory");
+
+        for options in [
+            options(None, QuotingStyle::Literal),
+            DisplayOptions {
+                list: true,
+                ..options(None, QuotingStyle::Literal)
+            },
+            DisplayOptions {
+                format: Format::Json,
+                ..options(None, QuotingStyle::Literal)
+            },
+        ] {
+            let error = directory
+                .display_output(&mut ClosedPipe, &80, &options)
+                .expect_err("Rendered into a closed pipe");
+
+            assert!(error.is_broken_pipe());
+        }
     }
 }
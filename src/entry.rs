@@ -0,0 +1,439 @@
+use std::{
+    ffi::OsString,
+    fs::{self, Metadata},
+    io,
+    os::unix::fs::{FileTypeExt, MetadataExt},
+    path::PathBuf,
+    sync::OnceLock,
+    time::SystemTime,
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use uzers::Group;
+
+use crate::{lines, mime::Mime};
+
+/// Extended attributes holding POSIX ACLs
+const ACLS: [&str; 2] = ["system.posix_acl_access", "system.posix_acl_default"];
+
+/// Extended attribute holding the `SELinux` security context
+const SELINUX: &str = "security.selinux";
+
+/// The type of an entry, as seen without following symbolic links
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    #[value(name = "f", alias = "file")]
+    File,
+    #[value(name = "d", alias = "directory")]
+    Directory,
+    #[value(name = "l", alias = "symlink")]
+    Symlink,
+    #[value(name = "p", alias = "pipe")]
+    Pipe,
+    #[value(name = "s", alias = "socket")]
+    Socket,
+    #[value(name = "b", alias = "block-device")]
+    BlockDevice,
+    #[value(name = "c", alias = "char-device")]
+    CharDevice,
+}
+
+impl Kind {
+    #[must_use]
+    pub fn of(metadata: &Metadata) -> Option<Self> {
+        let file_type = metadata.file_type();
+
+        if file_type.is_symlink() {
+            Some(Self::Symlink)
+        } else if file_type.is_dir() {
+            Some(Self::Directory)
+        } else if file_type.is_file() {
+            Some(Self::File)
+        } else if file_type.is_fifo() {
+            Some(Self::Pipe)
+        } else if file_type.is_socket() {
+            Some(Self::Socket)
+        } else if file_type.is_block_device() {
+            Some(Self::BlockDevice)
+        } else if file_type.is_char_device() {
+            Some(Self::CharDevice)
+        } else {
+            None
+        }
+    }
+}
+
+/// Which entries get a character after their name telling their type
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Indicator {
+    #[default]
+    None,
+    /// `/` after directories
+    Slash,
+    /// `/` after directories, `*` after executables, `@` after symbolic links,
+    /// `|` after pipes and `=` after sockets
+    Classify,
+}
+
+/// The fields of `lstat`, left raw until they get rendered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stat {
+    /// File type and permission bits
+    pub mode: u32,
+    /// Size in bytes
+    pub size: u64,
+    pub links: u64,
+    pub inode: u64,
+    /// Id of the device holding the entry
+    pub device: u64,
+    pub uid: u32,
+    pub gid: u32,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    pub created: Option<SystemTime>,
+}
+
+impl From<&Metadata> for Stat {
+    fn from(metadata: &Metadata) -> Self {
+        Self {
+            mode: metadata.mode(),
+            size: metadata.len(),
+            links: metadata.nlink(),
+            inode: metadata.ino(),
+            device: metadata.dev(),
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            created: metadata.created().ok(),
+        }
+    }
+}
+
+/// Who the process lists directories as
+struct Credentials {
+    uid: u32,
+    gid: u32,
+    /// Supplementary groups
+    groups: Vec<u32>,
+}
+
+impl Credentials {
+    /// Read once, they do not change while listing
+    fn current() -> &'static Self {
+        static CREDENTIALS: OnceLock<Credentials> = OnceLock::new();
+
+        CREDENTIALS.get_or_init(|| Self {
+            uid: uzers::get_effective_uid(),
+            gid: uzers::get_effective_gid(),
+            groups: uzers::group_access_list()
+                .map(|groups| groups.iter().map(Group::gid).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Works out from the mode bits whether a directory can be listed, without
+    /// opening it, which could mount it when it is an automount point. ACLs are
+    /// not taken into account
+    fn can_list(&self, stat: &Stat) -> bool {
+        if self.uid == 0 {
+            return true;
+        }
+
+        let read = if stat.uid == self.uid {
+            0o400
+        } else if stat.gid == self.gid || self.groups.contains(&stat.gid) {
+            0o040
+        } else {
+            0o004
+        };
+
+        stat.mode & read != 0
+    }
+}
+
+/// A listed file, folder, link or special file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: OsString,
+    pub path: PathBuf,
+    /// `None` when the metadata cannot be read or the type is unknown
+    pub kind: Option<Kind>,
+    /// Dotfiles and entries matching a `--hidden` pattern
+    pub hidden: bool,
+    /// Directories and links to directories, listed along with the folders
+    pub folder: bool,
+    /// A folder whose entries cannot be listed, usually for lack of permissions
+    pub locked: bool,
+    /// `None` when the metadata cannot be read
+    pub stat: Option<Stat>,
+    /// Where the entry points to when it is a symbolic link
+    pub link_target: Option<PathBuf>,
+    /// Names of the extended attributes, only once [`Entry::read_xattrs`] ran
+    pub xattrs: Vec<OsString>,
+    /// The `SELinux` security context, only once [`Entry::read_xattrs`] ran
+    pub context: Option<String>,
+    /// The type of a regular file told by its content, only once [`Entry::sniff`] ran
+    pub mime: Option<Mime>,
+    /// Line count of a text file, only once [`Entry::count_lines`] ran
+    pub lines: Option<u64>,
+}
+
+impl Entry {
+    /// Reads the metadata of `path` without following symbolic links
+    ///
+    /// # Errors
+    /// Will return an error if the metadata cannot be read
+    pub fn new(path: PathBuf, name: OsString, hidden: bool) -> io::Result<Self> {
+        let metadata = fs::symlink_metadata(&path)?;
+        let kind = Kind::of(&metadata);
+
+        let link_target = if kind == Some(Kind::Symlink) {
+            fs::read_link(&path).ok()
+        } else {
+            None
+        };
+
+        // The directory itself, or the one a symbolic link points to
+        let directory = match kind {
+            Some(Kind::Directory) => Some(Stat::from(&metadata)),
+            Some(Kind::Symlink) => fs::metadata(&path)
+                .ok()
+                .filter(Metadata::is_dir)
+                .map(|metadata| Stat::from(&metadata)),
+            _ => None,
+        };
+
+        Ok(Self {
+            name,
+            folder: directory.is_some(),
+            locked: directory.is_some_and(|stat| !Credentials::current().can_list(&stat)),
+            path,
+            kind,
+            hidden,
+            stat: Some(Stat::from(&metadata)),
+            link_target,
+            xattrs: Vec::new(),
+            context: None,
+            mime: None,
+            lines: None,
+        })
+    }
+
+    /// An entry whose metadata cannot be read, listed by name only
+    #[must_use]
+    pub const fn unreadable(path: PathBuf, name: OsString, hidden: bool) -> Self {
+        Self {
+            name,
+            path,
+            kind: None,
+            hidden,
+            folder: false,
+            locked: false,
+            stat: None,
+            link_target: None,
+            xattrs: Vec::new(),
+            context: None,
+            mime: None,
+            lines: None,
+        }
+    }
+
+    /// Reads the names of the extended attributes and the `SELinux` security
+    /// context, entries whose attributes cannot be read are left without any
+    pub fn read_xattrs(&mut self) {
+        self.xattrs = xattr::list(&self.path)
+            .map(Iterator::collect::<Vec<_>>)
+            .unwrap_or_default();
+
+        self.context = if self.xattrs.iter().any(|name| name == SELINUX) {
+            xattr::get(&self.path, SELINUX).ok().flatten().map(|value| {
+                String::from_utf8_lossy(&value)
+                    .trim_end_matches('\0')
+                    .to_string()
+            })
+        } else {
+            None
+        };
+    }
+
+    /// Reads the first bytes of a regular file to tell its type, other
+    /// entries and files that cannot be read are left without one
+    pub fn sniff(&mut self) {
+        if self.kind == Some(Kind::File) {
+            self.mime = Mime::of(&self.path).ok();
+        }
+    }
+
+    /// Counts the lines of a text file no larger than `max` bytes, binaries,
+    /// larger files and files that cannot be read are left without a count
+    pub fn count_lines(&mut self, max: u64) {
+        if self.kind == Some(Kind::File) && self.stat.is_some_and(|stat| stat.size <= max) {
+            self.lines = lines::count(&self.path).ok().flatten();
+        }
+    }
+
+    /// A regular file with any of the execute bits set
+    #[must_use]
+    pub fn is_executable(&self) -> bool {
+        self.kind == Some(Kind::File) && self.stat.is_some_and(|stat| stat.mode & 0o111 != 0)
+    }
+
+    /// The character appended to the name with `-p` or `-F`, like `ls` does
+    #[must_use]
+    pub fn indicator(&self, style: Indicator) -> Option<char> {
+        match (style, self.kind) {
+            (Indicator::Slash | Indicator::Classify, Some(Kind::Directory)) => Some('/'),
+            (Indicator::Classify, Some(Kind::Symlink)) => Some('@'),
+            (Indicator::Classify, Some(Kind::Pipe)) => Some('|'),
+            (Indicator::Classify, Some(Kind::Socket)) => Some('='),
+            (Indicator::Classify, _) if self.is_executable() => Some('*'),
+            _ => None,
+        }
+    }
+
+    /// `+` after the mode when the entry has a POSIX ACL, `@` when it has other
+    /// extended attributes besides its security context
+    #[must_use]
+    pub fn marker(&self) -> Option<char> {
+        if self
+            .xattrs
+            .iter()
+            .any(|name| ACLS.iter().any(|acl| name == acl))
+        {
+            Some('+')
+        } else if self.xattrs.iter().any(|name| name != SELINUX) {
+            Some('@')
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs, path::Path};
+
+    use crate::{
+        entry::{Credentials, Entry, Indicator, Kind, Stat},
+        mime::Mime,
+    };
+
+    #[test]
+    fn test_new() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(root.path().join("notes.txt"), b"hello").expect("Failed to write file");
+        fs::create_dir(root.path().join("src")).expect("Failed to create directory");
+        std::os::unix::fs::symlink("src", root.path().join("link")).expect("Failed to link");
+
+        let entry = |name: &str| {
+            Entry::new(
+                root.path().join(name),
+                OsString::from(name),
+                name.starts_with('.'),
+            )
+            .expect("Failed to read entry")
+        };
+
+        let file = entry("notes.txt");
+
+        assert_eq!(file.kind, Some(Kind::File));
+        assert!(!file.folder);
+        assert_eq!(file.stat.map(|stat| stat.size), Some(5));
+        assert_eq!(file.stat.map(|stat| stat.mode & 0o170_000), Some(0o100_000));
+        assert_eq!(file.link_target, None);
+
+        let link = entry("link");
+
+        assert_eq!(link.kind, Some(Kind::Symlink));
+        assert!(link.folder);
+        assert!(!link.locked);
+        assert_eq!(link.link_target.as_deref(), Some(Path::new("src")));
+
+        assert!(!file.is_executable());
+        assert_eq!(file.indicator(Indicator::Classify), None);
+        assert_eq!(entry("src").indicator(Indicator::Slash), Some('/'));
+        assert_eq!(link.indicator(Indicator::Slash), None);
+        assert_eq!(link.indicator(Indicator::Classify), Some('@'));
+        assert_eq!(entry("src").indicator(Indicator::None), None);
+
+        let mut script = entry("notes.txt");
+        if let Some(stat) = script.stat.as_mut() {
+            stat.mode |= 0o100;
+        }
+
+        assert!(script.is_executable());
+        assert_eq!(script.indicator(Indicator::Classify), Some('*'));
+        assert_eq!(script.indicator(Indicator::Slash), None);
+
+        let mut sniffed = entry("notes.txt");
+        let mut folder = entry("src");
+        sniffed.sniff();
+        folder.sniff();
+
+        assert_eq!(file.mime, None);
+        assert_eq!(sniffed.mime, Some(Mime::Text));
+        assert_eq!(folder.mime, None);
+
+        let mut tagged = entry("notes.txt");
+
+        assert_eq!(tagged.marker(), None);
+
+        tagged.xattrs = vec![OsString::from("security.selinux")];
+        assert_eq!(tagged.marker(), None);
+
+        tagged.xattrs.push(OsString::from("user.origin"));
+        assert_eq!(tagged.marker(), Some('@'));
+
+        tagged
+            .xattrs
+            .push(OsString::from("system.posix_acl_access"));
+        assert_eq!(tagged.marker(), Some('+'));
+
+        let missing = Entry::new(
+            root.path().join("missing"),
+            OsString::from("missing"),
+            false,
+        );
+
+        assert!(missing.is_err());
+    }
+
+    #[test]
+    fn test_can_list() {
+        let credentials = Credentials {
+            uid: 1000,
+            gid: 1000,
+            groups: vec![27],
+        };
+        let stat = |mode: u32, uid: u32, gid: u32| Stat {
+            mode: 0o040_000 | mode,
+            size: 4096,
+            links: 2,
+            inode: 1,
+            device: 0,
+            uid,
+            gid,
+            modified: None,
+            accessed: None,
+            created: None,
+        };
+
+        assert!(credentials.can_list(&stat(0o700, 1000, 0)));
+        assert!(!credentials.can_list(&stat(0o077, 1000, 1000)));
+        assert!(credentials.can_list(&stat(0o050, 0, 27)));
+        assert!(!credentials.can_list(&stat(0o705, 0, 1000)));
+        assert!(credentials.can_list(&stat(0o004, 0, 0)));
+        assert!(!credentials.can_list(&stat(0o770, 0, 0)));
+
+        let root = Credentials {
+            uid: 0,
+            gid: 0,
+            groups: Vec::new(),
+        };
+
+        assert!(root.can_list(&stat(0o000, 1000, 1000)));
+    }
+}
@@ -0,0 +1,122 @@
+use std::{error, fmt, io, path::PathBuf};
+
+/// Everything that can go wrong while listing a directory
+#[derive(Debug)]
+pub enum Error {
+    /// The metadata of a path cannot be read
+    Access { path: PathBuf, source: io::Error },
+    /// The directory cannot be opened
+    OpenDirectory { path: PathBuf, source: io::Error },
+    /// Reading the entries of an opened directory failed part way
+    ReadDirectory { path: PathBuf, source: io::Error },
+    /// The listing cannot be written out
+    Write(io::Error),
+    /// The config file has an invalid line
+    Config(String),
+}
+
+impl Error {
+    /// The reader of the output went away, like `head` does once it has its lines
+    #[must_use]
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, Self::Write(source) if source.kind() == io::ErrorKind::BrokenPipe)
+    }
+}
+
+/// The message of the error without the ` (os error 13)` suffix, the way `ls` prints it
+fn reason(error: &io::Error) -> String {
+    let message = error.to_string();
+
+    match error.raw_os_error() {
+        Some(code) => message
+            .strip_suffix(&format!(" (os error {code})"))
+            .map_or_else(|| message.clone(), ToString::to_string),
+        None => message,
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Access { path, source } => {
+                write!(f, "cannot access '{}': {}", path.display(), reason(source))
+            }
+            Self::OpenDirectory { path, source } => write!(
+                f,
+                "cannot open directory '{}': {}",
+                path.display(),
+                reason(source)
+            ),
+            Self::ReadDirectory { path, source } => write!(
+                f,
+                "reading directory '{}': {}",
+                path.display(),
+                reason(source)
+            ),
+            Self::Write(source) => write!(f, "write error: {}", reason(source)),
+            Self::Config(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Access { source, .. }
+            | Self::OpenDirectory { source, .. }
+            | Self::ReadDirectory { source, .. }
+            | Self::Write(source) => Some(source),
+            Self::Config(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{error::Error as _, io, path::PathBuf};
+
+    use crate::error::Error;
+
+    #[test]
+    fn test_display() {
+        let error = Error::Access {
+            path: PathBuf::from("secret"),
+            source: io::Error::from_raw_os_error(13),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "cannot access 'secret': Permission denied"
+        );
+        assert!(error.source().is_some());
+
+        let error = Error::OpenDirectory {
+            path: PathBuf::from("missing"),
+            source: io::Error::from_raw_os_error(2),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "cannot open directory 'missing': No such file or directory"
+        );
+
+        let error = Error::Write(io::Error::other("disk full"));
+
+        assert_eq!(error.to_string(), "write error: disk full");
+        assert_eq!(
+            Error::Config(String::from("line 1: unknown key colour")).to_string(),
+            "line 1: unknown key colour"
+        );
+    }
+
+    #[test]
+    fn test_is_broken_pipe() {
+        assert!(Error::Write(io::Error::from(io::ErrorKind::BrokenPipe)).is_broken_pipe());
+        assert!(!Error::Write(io::Error::other("disk full")).is_broken_pipe());
+        assert!(!Error::Access {
+            path: PathBuf::from("x"),
+            source: io::Error::from(io::ErrorKind::BrokenPipe),
+        }
+        .is_broken_pipe());
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    ffi::OsStr,
+    os::unix::ffi::OsStrExt,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    entry::{Entry, Kind},
+    git::ignore::Ignore,
+    glob::Pattern,
+    support,
+};
+
+/// Decides which entries of a directory get listed
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    /// Entries matching any of these are left out
+    pub ignore: Vec<Pattern>,
+    /// When not empty, only entries matching one of these are listed
+    pub only: Vec<Pattern>,
+    /// Leave out backup files, the ones ending with `~`
+    pub ignore_backups: bool,
+    /// Leave out entries ignored by git
+    pub git_ignore: Option<Ignore>,
+    /// Entries matching any of these are hidden just like dotfiles
+    pub hidden: Vec<Pattern>,
+    /// When not empty, only entries of one of these types are listed
+    pub types: Vec<Kind>,
+    /// Only list files larger than this many bytes
+    pub larger: Option<u64>,
+    /// Only list files smaller than this many bytes
+    pub smaller: Option<u64>,
+    /// Only list entries modified after this time
+    pub newer: Option<SystemTime>,
+    /// Only list entries modified before this time
+    pub older: Option<SystemTime>,
+}
+
+/// Parses a size such as `512`, `10k`, `1.5M` or `1GiB`, units are powers of 1024
+///
+/// # Errors
+/// Will return an error if the number or the unit is not valid
+pub fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let split = size
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+
+    let number = number
+        .parse::<f64>()
+        .map_err(|_| format!("Invalid size: {size}"))?;
+
+    let unit = unit.to_ascii_lowercase();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+
+    let exponent = match unit {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return Err(format!("Invalid size unit: {unit}")),
+    };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok((number * 1024f64.powi(exponent)) as u64)
+}
+
+/// Parses a duration such as `30s`, `15m`, `12h`, `7d` or `2w`, plain numbers are seconds
+///
+/// # Errors
+/// Will return an error if the number or the unit is not valid
+pub fn parse_duration(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+    let split = duration
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(duration.len());
+    let (number, unit) = duration.split_at(split);
+
+    let number = number
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid duration: {duration}"))?;
+
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("Invalid duration unit: {unit}")),
+    };
+
+    number
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("Duration too long: {duration}"))
+}
+
+impl Filter {
+    #[must_use]
+    pub fn is_hidden(&self, name: &OsStr) -> bool {
+        support::is_hidden(name) || self.hidden.iter().any(|pattern| pattern.matches(name))
+    }
+
+    #[must_use]
+    pub fn allows(&self, name: &OsStr, is_dir: bool) -> bool {
+        if self.ignore_backups && name.as_bytes().last() == Some(&b'~') {
+            return false;
+        }
+
+        if self
+            .git_ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.is_ignored(name, is_dir))
+        {
+            return false;
+        }
+
+        if self.ignore.iter().any(|pattern| pattern.matches(name)) {
+            return false;
+        }
+
+        self.only.is_empty() || self.only.iter().any(|pattern| pattern.matches(name))
+    }
+
+    /// Checks the type, size and age filters, entries without metadata are
+    /// only kept while none of them is set since they cannot be shown to match
+    #[must_use]
+    pub fn keeps(&self, entry: &Entry) -> bool {
+        let Some(stat) = entry.stat else {
+            return self.types.is_empty()
+                && self.larger.is_none()
+                && self.smaller.is_none()
+                && self.newer.is_none()
+                && self.older.is_none();
+        };
+
+        if !self.types.is_empty() && !entry.kind.is_some_and(|kind| self.types.contains(&kind)) {
+            return false;
+        }
+
+        if self.larger.is_some() || self.smaller.is_some() {
+            if entry.kind == Some(Kind::Directory) {
+                return false;
+            }
+
+            let size = stat.size;
+
+            if self.larger.is_some_and(|larger| size <= larger)
+                || self.smaller.is_some_and(|smaller| size >= smaller)
+            {
+                return false;
+            }
+        }
+
+        if self.newer.is_some() || self.older.is_some() {
+            let Some(modified) = stat.modified else {
+                return false;
+            };
+
+            if self.newer.is_some_and(|newer| modified <= newer)
+                || self.older.is_some_and(|older| modified >= older)
+            {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{OsStr, OsString},
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use crate::{
+        entry::{Entry, Kind},
+        filter::{parse_duration, parse_size, Filter},
+        git::ignore::Ignore,
+        glob::Pattern,
+    };
+
+    #[test]
+    fn test_allows_everything_by_default() {
+        let filter = Filter::default();
+
+        assert!(filter.allows(OsStr::new("anything"), false));
+        assert!(filter.allows(OsStr::new("backup~"), false));
+    }
+
+    #[test]
+    fn test_ignore() {
+        let filter = Filter {
+            ignore: vec![Pattern::new("target"), Pattern::new("*.o")],
+            ..Filter::default()
+        };
+
+        assert!(!filter.allows(OsStr::new("target"), false));
+        assert!(!filter.allows(OsStr::new("main.o"), false));
+        assert!(filter.allows(OsStr::new("src"), false));
+    }
+
+    #[test]
+    fn test_only() {
+        let filter = Filter {
+            only: vec![Pattern::new("*.log")],
+            ignore: vec![Pattern::new("debug.*")],
+            ..Filter::default()
+        };
+
+        assert!(filter.allows(OsStr::new("build.log"), false));
+        assert!(!filter.allows(OsStr::new("debug.log"), false));
+        assert!(!filter.allows(OsStr::new("build.txt"), false));
+    }
+
+    #[test]
+    fn test_git_ignore() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        std::fs::write(root.path().join(".gitignore"), "target/\n").expect("Failed to write file");
+
+        let filter = Filter {
+            git_ignore: Some(Ignore::load(root.path(), &root.path().join(".git"), b"")),
+            ..Filter::default()
+        };
+
+        assert!(!filter.allows(OsStr::new("target"), true));
+        assert!(filter.allows(OsStr::new("target"), false));
+    }
+
+    #[test]
+    fn test_ignore_backups() {
+        let filter = Filter {
+            ignore_backups: true,
+            ..Filter::default()
+        };
+
+        assert!(!filter.allows(OsStr::new("notes.txt~"), false));
+        assert!(filter.allows(OsStr::new("notes.txt"), false));
+    }
+
+    #[test]
+    fn test_hidden() {
+        let filter = Filter {
+            hidden: vec![Pattern::new("__pycache__"), Pattern::new("*.swp")],
+            ..Filter::default()
+        };
+
+        assert!(filter.is_hidden(OsStr::new(".git")));
+        assert!(filter.is_hidden(OsStr::new("__pycache__")));
+        assert!(filter.is_hidden(OsStr::new(".main.rs.swp")));
+        assert!(!filter.is_hidden(OsStr::new("main.rs")));
+        assert!(!Filter::default().is_hidden(OsStr::new("__pycache__")));
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("1.5M"), Ok(1024 * 1024 * 3 / 2));
+        assert_eq!(parse_size("1GiB"), Ok(1 << 30));
+        assert_eq!(parse_size("2gb"), Ok(2 << 30));
+        assert!(parse_size("1x").is_err());
+        assert!(parse_size("G").is_err());
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("15m"), Ok(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_duration("7d"), Ok(Duration::from_secs(7 * 24 * 3600)));
+        assert_eq!(
+            parse_duration("2w"),
+            Ok(Duration::from_secs(14 * 24 * 3600))
+        );
+        assert!(parse_duration("1y").is_err());
+        assert!(parse_duration("d").is_err());
+    }
+
+    #[test]
+    fn test_keeps() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(root.path().join("big"), vec![0; 2048]).expect("Failed to write file");
+        fs::write(root.path().join("small"), b"hi").expect("Failed to write file");
+        std::os::unix::fs::symlink("big", root.path().join("link")).expect("Failed to link");
+
+        let entry = |name: &str| {
+            Entry::new(root.path().join(name), OsString::from(name), false)
+                .expect("Failed to read entry")
+        };
+
+        let filter = Filter {
+            types: vec![Kind::File],
+            larger: Some(1024),
+            ..Filter::default()
+        };
+
+        assert!(filter.keeps(&entry("big")));
+        assert!(!filter.keeps(&entry("small")));
+        assert!(!filter.keeps(&entry("link")));
+        assert!(!filter.keeps(
+            &Entry::new(root.path().to_path_buf(), OsString::from("."), true)
+                .expect("Failed to read entry")
+        ));
+
+        let secret = Entry::unreadable(root.path().join("secret"), OsString::from("secret"), false);
+
+        assert!(!filter.keeps(&secret));
+        assert!(Filter::default().keeps(&secret));
+
+        let filter = Filter {
+            types: vec![Kind::Symlink, Kind::Directory],
+            ..Filter::default()
+        };
+
+        assert!(filter.keeps(&entry("link")));
+        assert!(!filter.keeps(&entry("small")));
+
+        let now = SystemTime::now();
+        let newer = Filter {
+            newer: now.checked_sub(Duration::from_secs(3600)),
+            ..Filter::default()
+        };
+        let older = Filter {
+            older: now.checked_sub(Duration::from_secs(3600)),
+            ..Filter::default()
+        };
+
+        assert!(newer.keeps(&entry("small")));
+        assert!(!older.keeps(&entry("small")));
+        assert!(Filter::default().keeps(&entry("link")));
+    }
+}
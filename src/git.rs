@@ -0,0 +1,492 @@
+pub mod ignore;
+pub mod index;
+pub mod object;
+
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fmt, fs, io,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{MetadataExt, PermissionsExt},
+    },
+    path::{Path, PathBuf},
+};
+
+use sha1_smol::Sha1;
+
+use crate::git::{
+    ignore::Ignore,
+    index::Entry,
+    object::{parse_hex, Objects, Oid},
+};
+
+/// State of an entry compared to the last commit (staged) or to the index (worktree)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    #[default]
+    Unchanged,
+    Ignored,
+    New,
+    Modified,
+}
+
+impl Change {
+    const fn symbol(self) -> (char, &'static str) {
+        match self {
+            Self::Unchanged => ('-', "90"),
+            Self::Ignored => ('I', "90"),
+            Self::New => ('N', "32"),
+            Self::Modified => ('M', "34"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GitStatus {
+    pub staged: Change,
+    pub worktree: Change,
+}
+
+impl GitStatus {
+    fn merge(&mut self, other: Self) {
+        self.staged = self.staged.max(other.staged);
+        self.worktree = self.worktree.max(other.worktree);
+    }
+
+    /// The two letters without colours
+    #[must_use]
+    pub fn letters(&self) -> String {
+        [self.staged.symbol().0, self.worktree.symbol().0]
+            .iter()
+            .collect()
+    }
+}
+
+/// Two coloured letters, the staged change followed by the worktree change
+impl fmt::Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (staged, staged_color) = self.staged.symbol();
+        let (worktree, worktree_color) = self.worktree.symbol();
+
+        write!(
+            f,
+            "\x1B[{staged_color}m{staged}\x1B[{worktree_color}m{worktree}\x1B[0m"
+        )
+    }
+}
+
+/// The git status of every entry of a directory, by name
+#[derive(Debug, Default)]
+pub struct Statuses {
+    entries: HashMap<OsString, GitStatus>,
+}
+
+impl Statuses {
+    #[must_use]
+    pub fn get(&self, name: &OsStr) -> GitStatus {
+        self.entries.get(name).copied().unwrap_or_default()
+    }
+
+    fn update(&mut self, name: &[u8], status: GitStatus) {
+        self.entries
+            .entry(OsStr::from_bytes(name).to_os_string())
+            .or_default()
+            .merge(status);
+    }
+}
+
+pub struct Repository {
+    workdir: PathBuf,
+    /// Holds the index and `HEAD`, of the worktree only in a linked worktree
+    git_dir: PathBuf,
+    /// Holds the objects, the references and the excludes shared by every worktree
+    common_dir: PathBuf,
+}
+
+impl Repository {
+    /// Finds the repository containing `path` by looking for a `.git` in it or any parent
+    #[must_use]
+    pub fn discover(path: &Path) -> Option<Self> {
+        path.ancestors().find_map(|directory| {
+            let dot_git = directory.join(".git");
+
+            if dot_git.is_dir() {
+                return Some(Self::new(directory, dot_git));
+            }
+
+            let contents = fs::read_to_string(&dot_git).ok()?;
+            let git_dir = contents.strip_prefix("gitdir:")?.trim();
+
+            Some(Self::new(directory, directory.join(git_dir)))
+        })
+    }
+
+    /// A linked worktree names the directory it shares with the main one in `commondir`
+    fn new(workdir: &Path, git_dir: PathBuf) -> Self {
+        let common_dir = fs::read_to_string(git_dir.join("commondir"))
+            .map_or_else(|_| git_dir.clone(), |common| git_dir.join(common.trim()));
+
+        Self {
+            workdir: workdir.to_path_buf(),
+            git_dir,
+            common_dir,
+        }
+    }
+
+    /// Path of `directory` relative to the repository root, empty or ending with `/`
+    fn relative(&self, directory: &Path) -> Vec<u8> {
+        let mut relative = directory
+            .strip_prefix(&self.workdir)
+            .map_or_else(|_| Vec::new(), |path| path.as_os_str().as_bytes().to_vec());
+
+        if !relative.is_empty() {
+            relative.push(b'/');
+        }
+
+        relative
+    }
+
+    #[must_use]
+    pub fn ignore(&self, directory: &Path) -> Ignore {
+        Ignore::load(&self.workdir, &self.common_dir, &self.relative(directory))
+    }
+
+    /// `HEAD` and the references git keeps per worktree live next to the index,
+    /// the others are shared
+    fn reference_dir(&self, reference: &str) -> &Path {
+        let shared = reference.starts_with("refs/")
+            && !["refs/worktree/", "refs/bisect/", "refs/rewritten/"]
+                .iter()
+                .any(|private| reference.starts_with(private));
+
+        if shared {
+            &self.common_dir
+        } else {
+            &self.git_dir
+        }
+    }
+
+    fn head(&self) -> Option<Oid> {
+        let mut reference = String::from("HEAD");
+
+        for _ in 0..10 {
+            let path = self.reference_dir(&reference).join(&reference);
+
+            let Ok(contents) = fs::read_to_string(path) else {
+                return self.packed_reference(&reference);
+            };
+
+            match contents.trim().strip_prefix("ref:") {
+                Some(target) => reference = target.trim().to_string(),
+                None => return parse_hex(&contents),
+            }
+        }
+
+        None
+    }
+
+    fn packed_reference(&self, reference: &str) -> Option<Oid> {
+        fs::read_to_string(self.common_dir.join("packed-refs"))
+            .ok()?
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .find(|(_, name)| *name == reference)
+            .and_then(|(oid, _)| parse_hex(oid))
+    }
+
+    /// Works out the status of every entry in `directory`, folders get the
+    /// combined status of everything inside them
+    ///
+    /// # Errors
+    /// Will return an error if the index, the tree of `HEAD` or the directory
+    /// cannot be read, only a missing `HEAD` means there are no commits yet
+    pub fn statuses(&self, directory: &Path) -> io::Result<Statuses> {
+        let prefix = self.relative(directory);
+        let index = index::read(&self.git_dir.join("index"))?;
+
+        let head = match self.head() {
+            Some(commit) => Objects::open(&self.common_dir).tree_entries(&commit, &prefix)?,
+            None => HashMap::new(),
+        };
+
+        let mut statuses = Statuses::default();
+        let mut tracked = HashSet::new();
+
+        for entry in &index {
+            tracked.insert(entry.path.as_slice());
+
+            let Some(rest) = entry.path.strip_prefix(prefix.as_slice()) else {
+                continue;
+            };
+
+            let staged = match head.get(&entry.path) {
+                None => Change::New,
+                Some(oid) if *oid != entry.oid => Change::Modified,
+                Some(_) => Change::Unchanged,
+            };
+
+            let worktree = if entry.stage != 0 || self.is_modified(entry) {
+                Change::Modified
+            } else {
+                Change::Unchanged
+            };
+
+            statuses.update(first_component(rest), GitStatus { staged, worktree });
+        }
+
+        for path in head
+            .keys()
+            .filter(|path| !tracked.contains(path.as_slice()))
+        {
+            if let Some(rest) = path.strip_prefix(prefix.as_slice()) {
+                let status = GitStatus {
+                    staged: Change::Modified,
+                    worktree: Change::Unchanged,
+                };
+
+                statuses.update(first_component(rest), status);
+            }
+        }
+
+        let ignore = self.ignore(directory);
+
+        for item in directory.read_dir()? {
+            let item = item?;
+            let name = item.file_name();
+            let is_dir = item.file_type()?.is_dir();
+
+            let mut path = prefix.clone();
+            path.extend_from_slice(name.as_bytes());
+
+            if name == ".git" || tracked.contains(path.as_slice()) {
+                continue;
+            }
+
+            let worktree = if ignore.is_ignored(&name, is_dir) {
+                Change::Ignored
+            } else if !is_dir || self.has_untracked(&item.path(), &ignore.child(&name), &tracked) {
+                Change::New
+            } else {
+                continue;
+            };
+
+            let status = GitStatus {
+                staged: Change::Unchanged,
+                worktree,
+            };
+
+            statuses.update(name.as_bytes(), status);
+        }
+
+        Ok(statuses)
+    }
+
+    /// Checks the file against the index, by size and modification time first
+    /// and by hashing its contents only when those differ
+    fn is_modified(&self, entry: &Entry) -> bool {
+        let path = self.workdir.join(OsStr::from_bytes(&entry.path));
+
+        let Ok(metadata) = fs::symlink_metadata(&path) else {
+            return true;
+        };
+
+        if entry.mode == 0o160_000 {
+            return false;
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        if metadata.size() as u32 != entry.size {
+            return true;
+        }
+
+        if metadata.is_file()
+            && (metadata.permissions().mode() & 0o111 != 0) != (entry.mode & 0o111 != 0)
+        {
+            return true;
+        }
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        if (metadata.mtime() as u32, metadata.mtime_nsec() as u32) == entry.mtime {
+            return false;
+        }
+
+        let contents = if metadata.is_symlink() {
+            fs::read_link(&path).map(|target| target.as_os_str().as_bytes().to_vec())
+        } else {
+            fs::read(&path)
+        };
+
+        contents.map_or(true, |contents| blob_oid(&contents) != entry.oid)
+    }
+
+    /// Looks for a file in `directory` that is neither tracked nor ignored
+    fn has_untracked(&self, directory: &Path, ignore: &Ignore, tracked: &HashSet<&[u8]>) -> bool {
+        let Ok(items) = directory.read_dir() else {
+            return false;
+        };
+
+        items.flatten().any(|item| {
+            let name = item.file_name();
+            let path = item.path();
+            let is_dir = item.file_type().is_ok_and(|file_type| file_type.is_dir());
+
+            if name == ".git" || ignore.is_ignored(&name, is_dir) {
+                return false;
+            }
+
+            if is_dir {
+                return self.has_untracked(&path, &ignore.child(&name), tracked);
+            }
+
+            path.strip_prefix(&self.workdir)
+                .is_ok_and(|relative| !tracked.contains(relative.as_os_str().as_bytes()))
+        })
+    }
+}
+
+fn first_component(path: &[u8]) -> &[u8] {
+    path.split(|byte| *byte == b'/').next().unwrap_or(path)
+}
+
+fn blob_oid(contents: &[u8]) -> Oid {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", contents.len()).as_bytes());
+    hasher.update(contents);
+    hasher.digest().bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, fs};
+
+    use crate::git::{blob_oid, object::parse_hex, Change, GitStatus, Repository};
+
+    /// A version 2 index tracking the symbolic links `paths` to `target`
+    fn symlink_index(paths: &[&str], target: &str) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&u32::try_from(paths.len()).unwrap_or(0).to_be_bytes());
+
+        for path in paths {
+            let mut entry = vec![0; 62];
+            entry[24..28].copy_from_slice(&0o120_000u32.to_be_bytes());
+            entry[36..40].copy_from_slice(&u32::try_from(target.len()).unwrap_or(0).to_be_bytes());
+            entry[40..60].copy_from_slice(&blob_oid(target.as_bytes()));
+            entry[60..62].copy_from_slice(&u16::try_from(path.len()).unwrap_or(0).to_be_bytes());
+            entry.extend_from_slice(path.as_bytes());
+            entry.push(0);
+
+            while !entry.len().is_multiple_of(8) {
+                entry.push(0);
+            }
+
+            data.extend(entry);
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_blob_oid() {
+        let oid = blob_oid(b"");
+
+        assert_eq!(oid[..4], [0xe6, 0x9d, 0xe2, 0x9b]);
+    }
+
+    #[test]
+    fn test_discover() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir_all(root.path().join(".git")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("src/nested")).expect("Failed to create directory");
+
+        let repository =
+            Repository::discover(&root.path().join("src/nested")).expect("Failed to discover");
+
+        assert_eq!(repository.workdir, root.path());
+        assert_eq!(
+            repository.relative(&root.path().join("src/nested")),
+            b"src/nested/"
+        );
+    }
+
+    #[test]
+    fn test_discover_worktree() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        let main = root.path().join("main/.git");
+        let linked = main.join("worktrees/wt");
+        let oid = "0123456789abcdef0123456789abcdef01234567";
+
+        fs::create_dir_all(main.join("refs/heads")).expect("Failed to create directory");
+        fs::create_dir_all(&linked).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("wt")).expect("Failed to create directory");
+        fs::write(main.join("refs/heads/topic"), format!("{oid}\n")).expect("Failed to write file");
+        fs::write(linked.join("HEAD"), "ref: refs/heads/topic\n").expect("Failed to write file");
+        fs::write(linked.join("commondir"), "../..\n").expect("Failed to write file");
+        fs::write(
+            root.path().join("wt/.git"),
+            format!("gitdir: {}\n", linked.display()),
+        )
+        .expect("Failed to write file");
+
+        let repository = Repository::discover(&root.path().join("wt")).expect("Failed to discover");
+
+        assert_eq!(repository.git_dir, linked);
+        assert_eq!(repository.head(), parse_hex(oid));
+        assert!(repository.statuses(&root.path().join("wt")).is_err());
+    }
+
+    #[test]
+    fn test_statuses_without_commits() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir_all(root.path().join(".git")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("target")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("empty")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("src")).expect("Failed to create directory");
+        fs::write(root.path().join(".gitignore"), "target/\n").expect("Failed to write file");
+        fs::write(root.path().join("src/main.rs"), "").expect("Failed to write file");
+
+        let repository = Repository::discover(root.path()).expect("Failed to discover");
+        let statuses = repository
+            .statuses(root.path())
+            .expect("Failed to get statuses");
+
+        let worktree = |name: &str| statuses.get(OsStr::new(name)).worktree;
+
+        assert_eq!(worktree("target"), Change::Ignored);
+        assert_eq!(worktree("src"), Change::New);
+        assert_eq!(worktree(".gitignore"), Change::New);
+        assert_eq!(worktree("empty"), Change::Unchanged);
+        assert_eq!(statuses.get(OsStr::new("missing")), GitStatus::default());
+        assert_eq!(statuses.get(OsStr::new("src")).letters(), "-N");
+    }
+
+    #[test]
+    fn test_statuses_tracked_symlink_to_directory() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir_all(root.path().join(".git")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("a")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("sub")).expect("Failed to create directory");
+        fs::write(root.path().join("a/file"), "").expect("Failed to write file");
+        std::os::unix::fs::symlink("../a", root.path().join("sub/link")).expect("Failed to link");
+        fs::write(
+            root.path().join(".git/index"),
+            symlink_index(&["sub/link"], "../a"),
+        )
+        .expect("Failed to write index");
+
+        let repository = Repository::discover(root.path()).expect("Failed to discover");
+        let statuses = repository
+            .statuses(root.path())
+            .expect("Failed to get statuses");
+
+        assert_eq!(statuses.get(OsStr::new("sub")).worktree, Change::Unchanged);
+        assert_eq!(statuses.get(OsStr::new("a")).worktree, Change::New);
+
+        let statuses = repository
+            .statuses(&root.path().join("sub"))
+            .expect("Failed to get statuses");
+
+        assert_eq!(statuses.get(OsStr::new("link")).worktree, Change::Unchanged);
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+use crate::glob::Pattern;
+
+/// A single line of a `.gitignore` file
+#[derive(Clone, Debug)]
+struct Rule {
+    /// Directory of the `.gitignore` relative to the repository root,
+    /// empty or ending with `/`
+    base: Vec<u8>,
+    pattern: Pattern,
+    negated: bool,
+    directory_only: bool,
+}
+
+impl Rule {
+    fn parse(line: &str, base: &[u8]) -> Option<Self> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        let line = if line.ends_with("\\ ") {
+            line
+        } else {
+            line.trim_end_matches(' ')
+        };
+
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = line
+            .strip_prefix('!')
+            .map_or((false, line), |rest| (true, rest));
+
+        let (directory_only, line) = line
+            .strip_suffix('/')
+            .map_or((false, line), |rest| (true, rest));
+
+        let pattern = if line.contains('/') {
+            Pattern::new(line.trim_start_matches('/'))
+        } else {
+            Pattern::new(&format!("**/{line}"))
+        };
+
+        Some(Self {
+            base: base.to_vec(),
+            pattern,
+            negated,
+            directory_only,
+        })
+    }
+
+    fn matches(&self, path: &[u8], is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+
+        path.strip_prefix(self.base.as_slice())
+            .is_some_and(|rest| self.pattern.matches_str(&String::from_utf8_lossy(rest)))
+    }
+}
+
+/// The ignore rules that apply to the entries of one directory of a repository,
+/// from `.git/info/exclude` and every `.gitignore` between the root and the directory
+#[derive(Clone, Debug)]
+pub struct Ignore {
+    workdir: PathBuf,
+    rules: Vec<Rule>,
+    /// Path of the directory relative to the repository root, empty or ending with `/`
+    prefix: Vec<u8>,
+    /// The directory itself is ignored, and with it everything inside
+    ignored: bool,
+}
+
+impl Ignore {
+    #[must_use]
+    pub fn load(workdir: &Path, git_dir: &Path, relative: &[u8]) -> Self {
+        let mut ignore = Self {
+            workdir: workdir.to_path_buf(),
+            rules: Vec::new(),
+            prefix: Vec::new(),
+            ignored: false,
+        };
+
+        ignore.read_rules(&git_dir.join("info").join("exclude"));
+        ignore.read_rules(&workdir.join(".gitignore"));
+
+        for component in relative
+            .split(|byte| *byte == b'/')
+            .filter(|c| !c.is_empty())
+        {
+            ignore = ignore.child(OsStr::from_bytes(component));
+        }
+
+        ignore
+    }
+
+    fn read_rules(&mut self, path: &Path) {
+        let Ok(contents) = fs::read(path) else {
+            return;
+        };
+
+        self.rules.extend(
+            String::from_utf8_lossy(&contents)
+                .lines()
+                .filter_map(|line| Rule::parse(line, &self.prefix)),
+        );
+    }
+
+    /// The rules for the subdirectory `name` of this directory
+    #[must_use]
+    pub fn child(&self, name: &OsStr) -> Self {
+        let mut child = self.clone();
+
+        child.ignored = self.is_ignored(name, true);
+        child.prefix.extend_from_slice(name.as_bytes());
+        child.prefix.push(b'/');
+
+        let directory = self.workdir.join(OsStr::from_bytes(&child.prefix));
+        child.read_rules(&directory.join(".gitignore"));
+
+        child
+    }
+
+    #[must_use]
+    pub fn is_ignored(&self, name: &OsStr, is_dir: bool) -> bool {
+        if self.ignored {
+            return true;
+        }
+
+        let mut path = self.prefix.clone();
+        path.extend_from_slice(name.as_bytes());
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(&path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, fs};
+
+    use crate::git::ignore::Ignore;
+
+    fn repository() -> tempfile::TempDir {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir_all(root.path().join(".git/info")).expect("Failed to create directory");
+        fs::create_dir_all(root.path().join("src/generated")).expect("Failed to create directory");
+
+        fs::write(
+            root.path().join(".gitignore"),
+            "target/\n*.log\n!keep.log\n/root.txt\n",
+        )
+        .expect("Failed to write .gitignore");
+        fs::write(root.path().join(".git/info/exclude"), "# comment\n*.swp\n")
+            .expect("Failed to write exclude");
+        fs::write(root.path().join("src/.gitignore"), "generated\n")
+            .expect("Failed to write .gitignore");
+
+        root
+    }
+
+    #[test]
+    fn test_is_ignored_root() {
+        let root = repository();
+        let ignore = Ignore::load(root.path(), &root.path().join(".git"), b"");
+
+        assert!(ignore.is_ignored(OsStr::new("target"), true));
+        assert!(!ignore.is_ignored(OsStr::new("target"), false));
+        assert!(ignore.is_ignored(OsStr::new("build.log"), false));
+        assert!(!ignore.is_ignored(OsStr::new("keep.log"), false));
+        assert!(ignore.is_ignored(OsStr::new("root.txt"), false));
+        assert!(ignore.is_ignored(OsStr::new(".main.rs.swp"), false));
+        assert!(!ignore.is_ignored(OsStr::new("src"), true));
+    }
+
+    #[test]
+    fn test_is_ignored_nested() {
+        let root = repository();
+        let ignore = Ignore::load(root.path(), &root.path().join(".git"), b"src/");
+
+        assert!(ignore.is_ignored(OsStr::new("generated"), true));
+        assert!(ignore.is_ignored(OsStr::new("debug.log"), false));
+        assert!(!ignore.is_ignored(OsStr::new("root.txt"), false));
+        assert!(!ignore.is_ignored(OsStr::new("main.rs"), false));
+    }
+
+    #[test]
+    fn test_is_ignored_inside_ignored_directory() {
+        let root = repository();
+        let ignore = Ignore::load(root.path(), &root.path().join(".git"), b"target/debug");
+
+        assert!(ignore.is_ignored(OsStr::new("anything"), false));
+    }
+}
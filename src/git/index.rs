@@ -0,0 +1,260 @@
+use std::{fs, io, path::Path};
+
+use crate::git::object::Oid;
+
+/// A file tracked in the index (the staging area)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    /// Path relative to the repository root
+    pub path: Vec<u8>,
+    pub oid: Oid,
+    pub mode: u32,
+    pub size: u32,
+    pub mtime: (u32, u32),
+    /// Non-zero while a merge conflict is unresolved
+    pub stage: u16,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn u32_at(data: &[u8], position: usize) -> io::Result<u32> {
+    data.get(position..position + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(|| invalid("Unexpected end of index"))
+}
+
+fn u16_at(data: &[u8], position: usize) -> io::Result<u16> {
+    data.get(position..position + 2)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u16::from_be_bytes)
+        .ok_or_else(|| invalid("Unexpected end of index"))
+}
+
+/// Length of the checksum closing the index
+const CHECKSUM_LENGTH: usize = 20;
+
+/// Mode of the directory entries a sparse index has in place of the files below them
+const SPARSE_DIRECTORY_MODE: u32 = 0o040_000;
+
+/// Reads the entries of the index file, a missing file is an empty index
+///
+/// # Errors
+/// Will return an error if the index cannot be read or is not a version 2, 3 or 4 index,
+/// or if it is a split or sparse index that does not list every tracked file by itself
+pub fn read(path: &Path) -> io::Result<Vec<Entry>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    parse(&data)
+}
+
+fn parse(data: &[u8]) -> io::Result<Vec<Entry>> {
+    if data.get(..4) != Some(b"DIRC") {
+        return Err(invalid("Not an index file"));
+    }
+
+    let version = u32_at(data, 4)?;
+
+    if !(2..=4).contains(&version) {
+        return Err(invalid("Unsupported index version"));
+    }
+
+    let count = u32_at(data, 8)?;
+
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut position = 12;
+
+    for _ in 0..count {
+        let start = position;
+
+        let mtime = (u32_at(data, start + 8)?, u32_at(data, start + 12)?);
+        let mode = u32_at(data, start + 24)?;
+        let size = u32_at(data, start + 36)?;
+
+        let oid = data
+            .get(start + 40..start + 60)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("Unexpected end of index"))?;
+
+        if mode == SPARSE_DIRECTORY_MODE {
+            return Err(invalid("Sparse indexes are not supported"));
+        }
+
+        let flags = u16_at(data, start + 60)?;
+        position = start + 62;
+
+        if version >= 3 && flags & 0x4000 != 0 {
+            position += 2;
+        }
+
+        let mut path = Vec::new();
+
+        if version == 4 {
+            let mut byte = *data
+                .get(position)
+                .ok_or_else(|| invalid("Unexpected end of index"))?;
+            let mut strip = usize::from(byte & 0x7f);
+
+            while byte & 0x80 != 0 {
+                position += 1;
+                byte = *data
+                    .get(position)
+                    .ok_or_else(|| invalid("Unexpected end of index"))?;
+                strip = ((strip + 1) << 7) | usize::from(byte & 0x7f);
+            }
+            position += 1;
+
+            let previous = entries
+                .last()
+                .map_or(&[][..], |entry| entry.path.as_slice());
+            let keep = previous
+                .len()
+                .checked_sub(strip)
+                .ok_or_else(|| invalid("Invalid path compression in index"))?;
+
+            path.extend_from_slice(&previous[..keep]);
+        }
+
+        let length = data
+            .get(position..)
+            .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+            .ok_or_else(|| invalid("Unexpected end of index"))?;
+
+        path.extend_from_slice(&data[position..position + length]);
+        position += length + 1;
+
+        if version < 4 {
+            position = start + (position - start).div_ceil(8) * 8;
+        }
+
+        entries.push(Entry {
+            path,
+            oid,
+            mode,
+            size,
+            mtime,
+            stage: (flags >> 12) & 0x3,
+        });
+    }
+
+    while data.len().saturating_sub(position) > CHECKSUM_LENGTH {
+        if data.get(position..position + 4) == Some(b"link") {
+            return Err(invalid("Split indexes are not supported"));
+        }
+
+        position += 8 + u32_at(data, position + 4)? as usize;
+    }
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::index::parse;
+
+    fn entry(path: &[u8], version: u32, strip: u8) -> Vec<u8> {
+        let mut entry = vec![0; 62];
+        entry[8..12].copy_from_slice(&7u32.to_be_bytes());
+        entry[24..28].copy_from_slice(&0o100_644u32.to_be_bytes());
+        entry[36..40].copy_from_slice(&13u32.to_be_bytes());
+        entry[40] = 0xab;
+        entry[60..62].copy_from_slice(&u16::try_from(path.len()).unwrap_or(0).to_be_bytes());
+
+        if version == 4 {
+            entry.push(strip);
+        }
+
+        entry.extend_from_slice(path);
+        entry.push(0);
+
+        if version < 4 {
+            while !entry.len().is_multiple_of(8) {
+                entry.push(0);
+            }
+        }
+
+        entry
+    }
+
+    fn index(version: u32, paths: &[&[u8]]) -> Vec<u8> {
+        let mut data = b"DIRC".to_vec();
+        data.extend_from_slice(&version.to_be_bytes());
+        data.extend_from_slice(&u32::try_from(paths.len()).unwrap_or(0).to_be_bytes());
+
+        let mut previous: &[u8] = b"";
+
+        for path in paths {
+            let shared = previous
+                .iter()
+                .zip(path.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let strip = u8::try_from(previous.len() - shared).unwrap_or(0);
+
+            if version == 4 {
+                data.extend(entry(&path[shared..], version, strip));
+            } else {
+                data.extend(entry(path, version, 0));
+            }
+
+            previous = path;
+        }
+
+        data
+    }
+
+    #[test]
+    fn test_parse_version_2() {
+        let entries = parse(&index(2, &[b"Cargo.toml", b"src/main.rs"])).expect("Failed to parse");
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, b"Cargo.toml");
+        assert_eq!(entries[1].path, b"src/main.rs");
+        assert_eq!(entries[1].mode, 0o100_644);
+        assert_eq!(entries[1].size, 13);
+        assert_eq!(entries[1].mtime, (7, 0));
+        assert_eq!(entries[1].oid[0], 0xab);
+    }
+
+    #[test]
+    fn test_parse_version_4() {
+        let entries = parse(&index(4, &[b"src/a.rs", b"src/b.rs"])).expect("Failed to parse");
+
+        assert_eq!(entries[0].path, b"src/a.rs");
+        assert_eq!(entries[1].path, b"src/b.rs");
+    }
+
+    #[test]
+    fn test_parse_extensions() {
+        let extended = |signature: &[u8]| {
+            let mut data = index(2, &[b"a.txt"]);
+            data.extend_from_slice(signature);
+            data.extend_from_slice(&4u32.to_be_bytes());
+            data.extend_from_slice(&[0; 4]);
+            data.extend_from_slice(&[0; 20]);
+            data
+        };
+
+        assert_eq!(
+            parse(&extended(b"UNTR")).map(|entries| entries.len()).ok(),
+            Some(1)
+        );
+        assert!(parse(&extended(b"link")).is_err());
+
+        let mut sparse = index(2, &[b"src/"]);
+        sparse[12 + 24..12 + 28].copy_from_slice(&0o040_000u32.to_be_bytes());
+
+        assert!(parse(&sparse).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse(b"NOPE").is_err());
+    }
+}
@@ -0,0 +1,545 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    fmt::Write,
+    fs::{self, File},
+    io::{self, Read},
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use flate2::read::ZlibDecoder;
+
+/// Id of a git object, the SHA-1 of its contents
+pub type Oid = [u8; 20];
+
+const TREE_MODE: &[u8] = b"40000";
+
+/// A line of a tree object
+struct TreeEntry {
+    name: Vec<u8>,
+    mode: Vec<u8>,
+    oid: Oid,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Commit,
+    Tree,
+    Blob,
+    Tag,
+}
+
+impl Kind {
+    fn from_name(name: &[u8]) -> io::Result<Self> {
+        match name {
+            b"commit" => Ok(Self::Commit),
+            b"tree" => Ok(Self::Tree),
+            b"blob" => Ok(Self::Blob),
+            b"tag" => Ok(Self::Tag),
+            _ => Err(invalid("Unknown object type")),
+        }
+    }
+
+    fn from_pack(kind: u8) -> io::Result<Self> {
+        match kind {
+            1 => Ok(Self::Commit),
+            2 => Ok(Self::Tree),
+            3 => Ok(Self::Blob),
+            4 => Ok(Self::Tag),
+            _ => Err(invalid("Unknown packed object type")),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[must_use]
+pub fn parse_hex(hex: &str) -> Option<Oid> {
+    let hex = hex.trim();
+
+    if hex.len() != 40 {
+        return None;
+    }
+
+    let mut oid = [0; 20];
+
+    for (index, byte) in oid.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(index * 2..index * 2 + 2)?, 16).ok()?;
+    }
+
+    Some(oid)
+}
+
+fn to_hex(oid: &Oid) -> String {
+    oid.iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
+
+/// The longest header of a packed object: its type and size, then either the
+/// distance to its delta base or the id of that base
+const PACK_HEADER_LENGTH: usize = 32;
+
+fn inflate(data: impl Read) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut output)?;
+    Ok(output)
+}
+
+fn byte_at(data: &[u8], position: usize) -> io::Result<u8> {
+    data.get(position)
+        .copied()
+        .ok_or_else(|| invalid("Unexpected end of object data"))
+}
+
+fn u32_at(data: &[u8], position: usize) -> io::Result<u32> {
+    data.get(position..position + 4)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u32::from_be_bytes)
+        .ok_or_else(|| invalid("Unexpected end of pack index"))
+}
+
+/// An object read once and shared by the deltas against it
+type Shared = (Kind, Rc<Vec<u8>>);
+
+/// Reads a pack file from an offset on, so only the object being inflated gets read
+struct PackReader<'a> {
+    file: &'a File,
+    offset: u64,
+}
+
+impl Read for PackReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.file.read_at(buf, self.offset)?;
+        self.offset += read as u64;
+        Ok(read)
+    }
+}
+
+struct Pack {
+    index: Vec<u8>,
+    path: PathBuf,
+    file: OnceCell<File>,
+    /// Delta bases already resolved, by offset, until [`Pack::forget_bases`]
+    bases: RefCell<HashMap<u64, Shared>>,
+}
+
+impl Pack {
+    fn open(index_path: &Path) -> io::Result<Self> {
+        let index = fs::read(index_path)?;
+
+        if index.get(..8) != Some(&[0xff, b't', b'O', b'c', 0, 0, 0, 2]) {
+            return Err(invalid("Only version 2 pack indexes are supported"));
+        }
+
+        Ok(Self {
+            index,
+            path: index_path.with_extension("pack"),
+            file: OnceCell::new(),
+            bases: RefCell::new(HashMap::new()),
+        })
+    }
+
+    fn file(&self) -> io::Result<&File> {
+        if let Some(file) = self.file.get() {
+            return Ok(file);
+        }
+
+        let file = File::open(&self.path)?;
+        Ok(self.file.get_or_init(|| file))
+    }
+
+    /// Inflates the compressed data starting at `offset`
+    fn inflate_at(&self, offset: u64) -> io::Result<Vec<u8>> {
+        inflate(PackReader {
+            file: self.file()?,
+            offset,
+        })
+    }
+
+    /// The object at `offset` when it is the base of a delta, kept for the next
+    /// deltas against it
+    fn base_at(&self, offset: u64, objects: &Objects) -> io::Result<Shared> {
+        if let Some((kind, base)) = self.bases.borrow().get(&offset) {
+            return Ok((*kind, Rc::clone(base)));
+        }
+
+        let (kind, base) = self.read_at(offset, objects)?;
+        let base = Rc::new(base);
+        self.bases
+            .borrow_mut()
+            .insert(offset, (kind, Rc::clone(&base)));
+
+        Ok((kind, base))
+    }
+
+    fn forget_bases(&self) {
+        self.bases.borrow_mut().clear();
+    }
+
+    /// Looks the object up in the fan-out table and sorted name list of the index
+    fn find(&self, oid: &Oid) -> io::Result<Option<u64>> {
+        let fanout = |byte: usize| u32_at(&self.index, 8 + byte * 4);
+
+        let count = fanout(255)? as usize;
+        let first = usize::from(oid[0]);
+
+        let mut low = if first == 0 {
+            0
+        } else {
+            fanout(first - 1)? as usize
+        };
+        let mut high = fanout(first)? as usize;
+
+        let names = 8 + 256 * 4;
+
+        while low < high {
+            let middle = usize::midpoint(low, high);
+
+            let name = self
+                .index
+                .get(names + middle * 20..names + middle * 20 + 20)
+                .ok_or_else(|| invalid("Unexpected end of pack index"))?;
+
+            match name.cmp(oid.as_slice()) {
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+                std::cmp::Ordering::Equal => {
+                    let offsets = names + count * 24;
+                    let offset = u32_at(&self.index, offsets + middle * 4)?;
+
+                    if offset & 0x8000_0000 == 0 {
+                        return Ok(Some(u64::from(offset)));
+                    }
+
+                    let large = offsets + count * 4 + (offset & 0x7fff_ffff) as usize * 8;
+                    let high = u64::from(u32_at(&self.index, large)?);
+                    let low = u64::from(u32_at(&self.index, large + 4)?);
+
+                    return Ok(Some(high << 32 | low));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn read_at(&self, offset: u64, objects: &Objects) -> io::Result<(Kind, Vec<u8>)> {
+        let mut header = [0; PACK_HEADER_LENGTH];
+        let length = self.file()?.read_at(&mut header, offset)?;
+        let data = &header[..length];
+
+        let mut position = 0;
+
+        let mut byte = byte_at(data, position)?;
+        let kind = (byte >> 4) & 7;
+
+        while byte & 0x80 != 0 {
+            position += 1;
+            byte = byte_at(data, position)?;
+        }
+        position += 1;
+
+        match kind {
+            6 => {
+                let mut byte = byte_at(data, position)?;
+                let mut distance = usize::from(byte & 0x7f);
+
+                while byte & 0x80 != 0 {
+                    position += 1;
+                    byte = byte_at(data, position)?;
+                    distance = ((distance + 1) << 7) | usize::from(byte & 0x7f);
+                }
+                position += 1;
+
+                let base_offset = offset
+                    .checked_sub(distance as u64)
+                    .ok_or_else(|| invalid("Delta base is out of range"))?;
+
+                let (kind, base) = self.base_at(base_offset, objects)?;
+                let delta = self.inflate_at(offset + position as u64)?;
+
+                Ok((kind, apply_delta(&base, &delta)?))
+            }
+            7 => {
+                let base_oid: Oid = data
+                    .get(position..position + 20)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| invalid("Unexpected end of pack data"))?;
+
+                let (kind, base) = if let Some(base_offset) = self.find(&base_oid)? {
+                    self.base_at(base_offset, objects)?
+                } else {
+                    let (kind, base) = objects.read(&base_oid)?;
+                    (kind, Rc::new(base))
+                };
+                let delta = self.inflate_at(offset + position as u64 + 20)?;
+
+                Ok((kind, apply_delta(&base, &delta)?))
+            }
+            kind => Ok((
+                Kind::from_pack(kind)?,
+                self.inflate_at(offset + position as u64)?,
+            )),
+        }
+    }
+}
+
+fn delta_size(delta: &[u8], position: &mut usize) -> io::Result<usize> {
+    let mut size = 0;
+    let mut shift = 0;
+
+    loop {
+        let byte = byte_at(delta, *position)?;
+        *position += 1;
+
+        size |= usize::from(byte & 0x7f) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+fn apply_delta(base: &[u8], delta: &[u8]) -> io::Result<Vec<u8>> {
+    let mut position = 0;
+
+    if delta_size(delta, &mut position)? != base.len() {
+        return Err(invalid("Delta does not match its base"));
+    }
+
+    let mut output = Vec::with_capacity(delta_size(delta, &mut position)?);
+
+    while position < delta.len() {
+        let instruction = delta[position];
+        position += 1;
+
+        if instruction & 0x80 != 0 {
+            let mut offset = 0;
+            let mut size = 0;
+
+            for bit in 0..4 {
+                if instruction & (1 << bit) != 0 {
+                    offset |= usize::from(byte_at(delta, position)?) << (8 * bit);
+                    position += 1;
+                }
+            }
+
+            for bit in 0..3 {
+                if instruction & (0x10 << bit) != 0 {
+                    size |= usize::from(byte_at(delta, position)?) << (8 * bit);
+                    position += 1;
+                }
+            }
+
+            if size == 0 {
+                size = 0x10000;
+            }
+
+            output.extend_from_slice(
+                base.get(offset..offset + size)
+                    .ok_or_else(|| invalid("Delta copies past its base"))?,
+            );
+        } else if instruction != 0 {
+            let size = usize::from(instruction);
+
+            output.extend_from_slice(
+                delta
+                    .get(position..position + size)
+                    .ok_or_else(|| invalid("Unexpected end of delta"))?,
+            );
+            position += size;
+        } else {
+            return Err(invalid("Invalid delta instruction"));
+        }
+    }
+
+    Ok(output)
+}
+
+/// The object database of a repository, both loose objects and packs
+pub struct Objects {
+    directory: PathBuf,
+    packs: Vec<Pack>,
+}
+
+impl Objects {
+    #[must_use]
+    pub fn open(git_dir: &Path) -> Self {
+        let directory = git_dir.join("objects");
+
+        let packs = fs::read_dir(directory.join("pack"))
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| path.extension().is_some_and(|extension| extension == "idx"))
+                    .filter_map(|path| Pack::open(&path).ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self { directory, packs }
+    }
+
+    /// # Errors
+    /// Will return an error if the object does not exist or cannot be decoded
+    pub fn read(&self, oid: &Oid) -> io::Result<(Kind, Vec<u8>)> {
+        let hex = to_hex(oid);
+        let loose = self.directory.join(&hex[..2]).join(&hex[2..]);
+
+        if let Ok(compressed) = fs::read(loose) {
+            let object = inflate(compressed.as_slice())?;
+
+            let header = object
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| invalid("Object without a header"))?;
+
+            let kind = object[..header]
+                .split(|byte| *byte == b' ')
+                .next()
+                .map_or_else(|| Err(invalid("Object without a type")), Kind::from_name)?;
+
+            return Ok((kind, object[header + 1..].to_vec()));
+        }
+
+        for pack in &self.packs {
+            if let Some(offset) = pack.find(oid)? {
+                return pack.read_at(offset, self);
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Object {hex} not found"),
+        ))
+    }
+
+    /// Every blob below `prefix` in the tree of the commit, keyed by its path
+    /// relative to the repository root
+    ///
+    /// # Errors
+    /// Will return an error if one of the objects cannot be read
+    pub fn tree_entries(&self, commit: &Oid, prefix: &[u8]) -> io::Result<HashMap<Vec<u8>, Oid>> {
+        let (_, commit) = self.read(commit)?;
+
+        let mut tree = commit
+            .strip_prefix(b"tree ")
+            .and_then(|rest| rest.get(..40))
+            .and_then(|hex| parse_hex(&String::from_utf8_lossy(hex)))
+            .ok_or_else(|| invalid("Commit without a tree"))?;
+
+        let mut entries = HashMap::new();
+
+        for component in prefix.split(|byte| *byte == b'/').filter(|c| !c.is_empty()) {
+            let Some(subtree) = self
+                .read_tree(&tree)?
+                .into_iter()
+                .find(|entry| entry.mode == TREE_MODE && entry.name == component)
+            else {
+                return Ok(entries);
+            };
+
+            tree = subtree.oid;
+        }
+
+        let flattened = self.flatten(&tree, prefix, &mut entries);
+
+        for pack in &self.packs {
+            pack.forget_bases();
+        }
+
+        flattened?;
+
+        Ok(entries)
+    }
+
+    fn flatten(
+        &self,
+        tree: &Oid,
+        path: &[u8],
+        entries: &mut HashMap<Vec<u8>, Oid>,
+    ) -> io::Result<()> {
+        for TreeEntry { name, mode, oid } in self.read_tree(tree)? {
+            let mut child = path.to_vec();
+            child.extend_from_slice(&name);
+
+            if mode == TREE_MODE {
+                child.push(b'/');
+                self.flatten(&oid, &child, entries)?;
+            } else {
+                entries.insert(child, oid);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_tree(&self, oid: &Oid) -> io::Result<Vec<TreeEntry>> {
+        let (kind, data) = self.read(oid)?;
+
+        if kind != Kind::Tree {
+            return Err(invalid("Expected a tree object"));
+        }
+
+        let mut entries = Vec::new();
+        let mut rest = data.as_slice();
+
+        while !rest.is_empty() {
+            let space = rest
+                .iter()
+                .position(|byte| *byte == b' ')
+                .ok_or_else(|| invalid("Tree entry without a mode"))?;
+            let null = rest
+                .iter()
+                .position(|byte| *byte == 0)
+                .ok_or_else(|| invalid("Tree entry without a name"))?;
+
+            let oid = rest
+                .get(null + 1..null + 21)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| invalid("Tree entry without an id"))?;
+
+            entries.push(TreeEntry {
+                name: rest[space + 1..null].to_vec(),
+                mode: rest[..space].to_vec(),
+                oid,
+            });
+
+            rest = &rest[null + 21..];
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::git::object::{apply_delta, parse_hex, to_hex};
+
+    #[test]
+    fn test_parse_hex() {
+        let hex = "0123456789abcdef0123456789abcdef01234567";
+        let oid = parse_hex(hex).expect("Failed to parse object id");
+
+        assert_eq!(oid[0], 0x01);
+        assert_eq!(to_hex(&oid), hex);
+        assert_eq!(parse_hex("0123"), None);
+    }
+
+    #[test]
+    fn test_apply_delta() {
+        let base = b"Hello, World!";
+        let delta = [13, 11, 0x91, 0, 7, 4, b'R', b'u', b's', b't'];
+
+        let result = apply_delta(base, &delta).expect("Failed to apply delta");
+
+        assert_eq!(result, b"Hello, Rust");
+    }
+}
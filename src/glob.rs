@@ -0,0 +1,248 @@
+use std::ffi::OsStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    /// `?`, any single character except `/`
+    Any,
+    /// `*`, any number of characters except `/`
+    Star,
+    /// `**`, any number of characters including `/`
+    DoubleStar,
+    /// `**/`, nothing or any number of directories
+    Dirs,
+    /// `[...]`, any single character in (or not in) the ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// A shell style wildcard pattern supporting `*`, `**`, `?`, `[...]`
+/// and backslash escapes, a `**/` also matches no directory at all
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Self::new(pattern)
+    }
+}
+
+impl Pattern {
+    /// Parses the pattern, an unclosed `[` or a trailing `\` are taken literally
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        let characters = pattern.chars().collect::<Vec<_>>();
+        let mut tokens = Vec::new();
+        let mut index = 0;
+
+        while index < characters.len() {
+            match characters[index] {
+                '*' if characters.get(index + 1) == Some(&'*') => {
+                    let starts_component = index == 0 || characters[index - 1] == '/';
+
+                    index += 1;
+
+                    while characters.get(index + 1) == Some(&'*') {
+                        index += 1;
+                    }
+
+                    if starts_component && characters.get(index + 1) == Some(&'/') {
+                        tokens.push(Token::Dirs);
+                        index += 1;
+                    } else {
+                        tokens.push(Token::DoubleStar);
+                    }
+                }
+                '*' => tokens.push(Token::Star),
+                '?' => tokens.push(Token::Any),
+                '\\' if index + 1 < characters.len() => {
+                    index += 1;
+                    tokens.push(Token::Literal(characters[index]));
+                }
+                '[' => {
+                    if let Some((class, end)) = Self::parse_class(&characters, index) {
+                        tokens.push(class);
+                        index = end;
+                    } else {
+                        tokens.push(Token::Literal('['));
+                    }
+                }
+                character => tokens.push(Token::Literal(character)),
+            }
+
+            index += 1;
+        }
+
+        Self { tokens }
+    }
+
+    /// Parses the class starting at `start`, returning it with the index of its `]`
+    fn parse_class(characters: &[char], start: usize) -> Option<(Token, usize)> {
+        let mut index = start + 1;
+
+        let negated = matches!(characters.get(index), Some('!' | '^'));
+        if negated {
+            index += 1;
+        }
+
+        let mut ranges = Vec::new();
+        let first = index;
+
+        while index < characters.len() {
+            let character = characters[index];
+
+            if character == ']' && index > first {
+                return Some((Token::Class { negated, ranges }, index));
+            }
+
+            if characters.get(index + 1) == Some(&'-')
+                && characters.get(index + 2).is_some_and(|end| *end != ']')
+            {
+                ranges.push((character, characters[index + 2]));
+                index += 3;
+            } else {
+                ranges.push((character, character));
+                index += 1;
+            }
+        }
+
+        None
+    }
+
+    #[must_use]
+    pub fn matches(&self, name: &OsStr) -> bool {
+        self.matches_str(&name.to_string_lossy())
+    }
+
+    /// Runs every possible position in the pattern side by side, so stars
+    /// never need backtracking
+    #[must_use]
+    pub fn matches_str(&self, text: &str) -> bool {
+        let mut current = vec![false; self.tokens.len() + 1];
+        self.enter(&mut current, 0);
+
+        for character in text.chars() {
+            let mut next = vec![false; self.tokens.len() + 1];
+
+            for (index, token) in self.tokens.iter().enumerate() {
+                if !current[index] {
+                    continue;
+                }
+
+                match token {
+                    Token::Literal(literal) if *literal == character => {
+                        self.enter(&mut next, index + 1);
+                    }
+                    Token::Any if character != '/' => self.enter(&mut next, index + 1),
+                    Token::Star if character != '/' => self.enter(&mut next, index),
+                    Token::DoubleStar => self.enter(&mut next, index),
+                    Token::Dirs => {
+                        next[index] = true;
+
+                        if character == '/' {
+                            self.enter(&mut next, index + 1);
+                        }
+                    }
+                    Token::Class { negated, ranges } if character != '/' => {
+                        let found = ranges
+                            .iter()
+                            .any(|(start, end)| (*start..=*end).contains(&character));
+
+                        if found != *negated {
+                            self.enter(&mut next, index + 1);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if !next.contains(&true) {
+                return false;
+            }
+
+            current = next;
+        }
+
+        current[self.tokens.len()]
+    }
+
+    /// Stars can match nothing, so reaching one also reaches the token after it
+    fn enter(&self, states: &mut [bool], mut index: usize) {
+        states[index] = true;
+
+        while let Some(Token::Star | Token::DoubleStar | Token::Dirs) = self.tokens.get(index) {
+            index += 1;
+            states[index] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::glob::Pattern;
+
+    #[test]
+    fn test_literal() {
+        assert!(Pattern::new("target").matches_str("target"));
+        assert!(!Pattern::new("target").matches_str("targets"));
+    }
+
+    #[test]
+    fn test_star() {
+        let pattern = Pattern::new("*.log");
+
+        assert!(pattern.matches_str("build.log"));
+        assert!(pattern.matches_str(".log"));
+        assert!(!pattern.matches_str("build.log.gz"));
+        assert!(!pattern.matches_str("logs/build.log"));
+        assert!(Pattern::new("a*b*c").matches_str("aXXbYYc"));
+    }
+
+    #[test]
+    fn test_double_star() {
+        let pattern = Pattern::new("logs/**");
+
+        assert!(pattern.matches_str("logs/a/b.log"));
+        assert!(!pattern.matches_str("src/a.log"));
+    }
+
+    #[test]
+    fn test_leading_dirs() {
+        let pattern = Pattern::new("**/target");
+
+        assert!(pattern.matches_str("target"));
+        assert!(pattern.matches_str("a/b/target"));
+        assert!(!pattern.matches_str("a/btarget"));
+        assert!(Pattern::new("a/**/b").matches_str("a/b"));
+        assert!(Pattern::new("a/**/b").matches_str("a/x/y/b"));
+        assert!(!Pattern::new("a/**/b").matches_str("ab"));
+    }
+
+    #[test]
+    fn test_any() {
+        assert!(Pattern::new("file?.txt").matches_str("file1.txt"));
+        assert!(Pattern::new("?.rs").matches_str("\u{e9}.rs"));
+        assert!(!Pattern::new("file?.txt").matches_str("file.txt"));
+    }
+
+    #[test]
+    fn test_class() {
+        let pattern = Pattern::new("[a-c]*");
+
+        assert!(pattern.matches_str("bin"));
+        assert!(!pattern.matches_str("dir"));
+        assert!(Pattern::new("[!a-c]*").matches_str("dir"));
+        assert!(Pattern::new("[]]").matches_str("]"));
+    }
+
+    #[test]
+    fn test_escapes_and_unclosed() {
+        assert!(Pattern::new("\\*").matches_str("*"));
+        assert!(!Pattern::new("\\*").matches_str("a"));
+        assert!(Pattern::new("[abc").matches_str("[abc"));
+    }
+}
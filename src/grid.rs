@@ -0,0 +1,297 @@
+/// Number of spaces between two columns
+pub const SEPARATOR: usize = 2;
+
+/// Number of terminal columns the grid can take up
+pub trait Width {
+    fn width(&self) -> usize;
+}
+
+/// A fixed width, for files and buffers
+impl Width for usize {
+    fn width(&self) -> usize {
+        *self
+    }
+}
+
+/// The width of the terminal, or `fallback` when the output is not one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Terminal {
+    pub fallback: usize,
+}
+
+impl Width for Terminal {
+    fn width(&self) -> usize {
+        term_size::dimensions().map_or(self.fallback, |(width, _)| width)
+    }
+}
+
+/// Order in which the entries fill the grid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Fill each column from top to bottom before moving to the next one
+    Down,
+    /// Fill each row from left to right before moving to the next one
+    Across,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Layout {
+    pub rows: usize,
+    pub widths: Vec<usize>,
+    direction: Direction,
+    count: usize,
+}
+
+impl Layout {
+    /// Finds the layout with the most columns that still fits in `width`,
+    /// each column being only as wide as its longest entry
+    ///
+    /// When not even two columns fit, which includes widths smaller than a
+    /// single entry, every entry gets a line of its own
+    #[must_use]
+    pub fn fit(cells: &[usize], width: usize, direction: Direction) -> Self {
+        let narrowest = cells.iter().min().map_or(0, |cell| *cell);
+
+        let max_columns = (width.saturating_add(SEPARATOR) / narrowest.saturating_add(SEPARATOR))
+            .min(cells.len());
+
+        for columns in (2..=max_columns).rev() {
+            let layout = Self::with_columns(cells, columns, direction);
+
+            if layout.total_width() <= width {
+                return layout;
+            }
+        }
+
+        Self::with_columns(cells, 1, direction)
+    }
+
+    fn with_columns(cells: &[usize], columns: usize, direction: Direction) -> Self {
+        let rows = cells.len().div_ceil(columns);
+
+        let columns = match direction {
+            Direction::Down => cells.len().div_ceil(rows.max(1)),
+            Direction::Across => columns,
+        };
+
+        let mut layout = Self {
+            rows,
+            widths: vec![0; columns],
+            direction,
+            count: cells.len(),
+        };
+
+        for (index, cell) in cells.iter().enumerate() {
+            let column = layout.column_of(index);
+
+            if layout.widths[column] < *cell {
+                layout.widths[column] = *cell;
+            }
+        }
+
+        layout
+    }
+
+    const fn column_of(&self, index: usize) -> usize {
+        match self.direction {
+            Direction::Down => index / self.rows,
+            Direction::Across => index % self.widths.len(),
+        }
+    }
+
+    /// Total number of columns taken up by the widest row
+    #[must_use]
+    pub fn total_width(&self) -> usize {
+        self.widths
+            .iter()
+            .fold(0, |total: usize, width| total.saturating_add(*width))
+            .saturating_add(SEPARATOR * self.widths.len().saturating_sub(1))
+    }
+
+    /// Spaces to print after an entry of `width` columns so that the next
+    /// entry in the row lines up, nothing is printed after the last one
+    #[must_use]
+    pub fn padding(&self, row: usize, column: usize, width: usize) -> usize {
+        if self.cell(row, column + 1).is_none() {
+            return 0;
+        }
+
+        self.widths
+            .get(column)
+            .map_or(0, |column_width| column_width.saturating_sub(width))
+            + SEPARATOR
+    }
+
+    /// Index of the entry displayed at the given row and column, if any
+    #[must_use]
+    pub fn cell(&self, row: usize, column: usize) -> Option<usize> {
+        if row >= self.rows || column >= self.widths.len() {
+            return None;
+        }
+
+        let index = match self.direction {
+            Direction::Down => column * self.rows + row,
+            Direction::Across => row * self.widths.len() + column,
+        };
+
+        (index < self.count).then_some(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::{
+        grid::{Direction, Layout},
+        support::display_width,
+    };
+
+    fn direction() -> impl Strategy<Value = Direction> {
+        prop_oneof![Just(Direction::Down), Just(Direction::Across)]
+    }
+
+    #[test]
+    fn test_fit_single_row() {
+        let layout = Layout::fit(&[3, 4, 5], 80, Direction::Down);
+
+        assert_eq!(layout.rows, 1);
+        assert_eq!(layout.widths, vec![3, 4, 5]);
+        assert_eq!(layout.total_width(), 16);
+    }
+
+    #[test]
+    fn test_fit_variable_widths() {
+        let layout = Layout::fit(&[2, 2, 10, 2, 2, 2], 20, Direction::Down);
+
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.widths, vec![2, 10, 2]);
+        assert!(layout.total_width() <= 20);
+    }
+
+    #[test]
+    fn test_fit_down() {
+        let layout = Layout::fit(&[1, 1, 1, 1, 1], 7, Direction::Down);
+
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.widths.len(), 3);
+        assert_eq!(layout.cell(0, 0), Some(0));
+        assert_eq!(layout.cell(1, 0), Some(1));
+        assert_eq!(layout.cell(0, 1), Some(2));
+        assert_eq!(layout.cell(1, 2), None);
+    }
+
+    #[test]
+    fn test_fit_across() {
+        let layout = Layout::fit(&[1, 1, 1, 1, 1], 7, Direction::Across);
+
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.widths.len(), 3);
+        assert_eq!(layout.cell(0, 1), Some(1));
+        assert_eq!(layout.cell(1, 0), Some(3));
+        assert_eq!(layout.cell(1, 2), None);
+    }
+
+    #[test]
+    fn test_fit_one_per_line() {
+        let layout = Layout::fit(&[30, 30, 30], 40, Direction::Down);
+
+        assert_eq!(layout.rows, 3);
+        assert_eq!(layout.widths, vec![30]);
+    }
+
+    #[test]
+    fn test_fit_narrower_than_entry() {
+        let layout = Layout::fit(&[10, 3], 4, Direction::Across);
+
+        assert_eq!(layout.rows, 2);
+        assert_eq!(layout.widths, vec![10]);
+        assert_eq!(layout.padding(0, 0, 10), 0);
+    }
+
+    #[test]
+    fn test_fit_zero_width() {
+        let layout = Layout::fit(&[1, 2, 3], 0, Direction::Down);
+
+        assert_eq!(layout.rows, 3);
+        assert_eq!(layout.widths.len(), 1);
+    }
+
+    #[test]
+    fn test_fit_empty() {
+        let layout = Layout::fit(&[], 80, Direction::Down);
+
+        assert_eq!(layout.rows, 0);
+        assert_eq!(layout.total_width(), 0);
+        assert_eq!(layout.cell(0, 0), None);
+    }
+
+    #[test]
+    fn test_padding() {
+        let layout = Layout::fit(&[3, 5, 4], 80, Direction::Across);
+
+        assert_eq!(layout.padding(0, 0, 3), 2);
+        assert_eq!(layout.padding(0, 1, 5), 2);
+        assert_eq!(layout.padding(0, 2, 4), 0);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_fit_places_every_entry_once(
+            cells in prop::collection::vec(0usize..120, 0..80),
+            width in 0usize..400,
+            direction in direction(),
+        ) {
+            let layout = Layout::fit(&cells, width, direction);
+            let mut seen = vec![false; cells.len()];
+
+            for row in 0..layout.rows {
+                for column in 0..layout.widths.len() {
+                    if let Some(index) = layout.cell(row, column) {
+                        prop_assert!(!seen[index]);
+                        prop_assert!(layout.widths[column] >= cells[index]);
+                        seen[index] = true;
+                    }
+                }
+            }
+
+            prop_assert!(seen.iter().all(|placed| *placed));
+        }
+
+        #[test]
+        fn prop_fit_within_width_or_one_per_line(
+            cells in prop::collection::vec(0usize..120, 1..80),
+            width in 0usize..400,
+            direction in direction(),
+        ) {
+            let layout = Layout::fit(&cells, width, direction);
+
+            if layout.total_width() > width {
+                prop_assert_eq!(layout.widths.len(), 1);
+                prop_assert_eq!(layout.rows, cells.len());
+            }
+        }
+
+        #[test]
+        fn prop_rows_never_exceed_width(
+            names in prop::collection::vec("\\PC{0,40}", 1..60),
+            width in 0usize..300,
+            direction in direction(),
+        ) {
+            let cells = names.iter().map(|name| display_width(name)).collect::<Vec<_>>();
+            let layout = Layout::fit(&cells, width, direction);
+
+            for row in 0..layout.rows {
+                let mut row_width = 0;
+
+                for column in 0..layout.widths.len() {
+                    if let Some(index) = layout.cell(row, column) {
+                        row_width += cells[index] + layout.padding(row, column, cells[index]);
+                    }
+                }
+
+                prop_assert!(layout.widths.len() == 1 || row_width <= width);
+            }
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use crate::mime::Mime;
+
+pub const DEFAULT_FILE: char = '\u{ea7b}';
+pub const DEFAULT_FOLDER: char = '\u{ea83}';
+
+/// The glyphs used for icons, every set uses single column characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IconSet {
+    /// Per-type icons, requires a nerd font
+    Nerd,
+    /// Plain unicode shapes that most fonts have
+    Unicode,
+    /// Plain ascii characters
+    Ascii,
+}
+
+impl IconSet {
+    #[must_use]
+    pub fn file(self, name: &str) -> char {
+        match self {
+            Self::Nerd => file_icon(name),
+            Self::Unicode => '\u{2022}',
+            Self::Ascii => '-',
+        }
+    }
+
+    #[must_use]
+    pub fn folder(self, name: &str) -> char {
+        match self {
+            Self::Nerd => folder_icon(name),
+            Self::Unicode => '\u{25b8}',
+            Self::Ascii => '+',
+        }
+    }
+
+    /// For files whose content tells more than their name, only nerd fonts have
+    /// distinct glyphs for them
+    #[must_use]
+    pub const fn content(self, mime: Mime) -> Option<char> {
+        match self {
+            Self::Nerd => content_icon(mime),
+            Self::Unicode | Self::Ascii => None,
+        }
+    }
+
+    /// For folders whose entries cannot be listed
+    #[must_use]
+    pub const fn locked(self) -> char {
+        match self {
+            Self::Nerd => '\u{f023}',
+            Self::Unicode => '\u{2298}',
+            Self::Ascii => '!',
+        }
+    }
+}
+
+#[must_use]
+pub fn file_icon(name: &str) -> char {
+    if let Some(icon) = file_name_icon(name) {
+        return icon;
+    }
+
+    Path::new(name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| extension_icon(&extension.to_ascii_lowercase()))
+        .unwrap_or(DEFAULT_FILE)
+}
+
+#[must_use]
+pub fn folder_icon(name: &str) -> char {
+    match name {
+        ".git" => '\u{e5fb}',
+        ".github" => '\u{e5fd}',
+        ".config" => '\u{e5fc}',
+        "node_modules" => '\u{e5fa}',
+        "src" | "lib" => '\u{f19fc}',
+        "target" | "build" | "dist" => '\u{f487}',
+        "Desktop" => '\u{f108}',
+        "Documents" => '\u{f02d}',
+        "Downloads" => '\u{f019}',
+        "Music" => '\u{f001}',
+        "Pictures" => '\u{f03e}',
+        "Videos" => '\u{f03d}',
+        _ => DEFAULT_FOLDER,
+    }
+}
+
+/// The icon of a sniffed type, `None` for plain text and binaries so the name still picks one
+#[must_use]
+pub const fn content_icon(mime: Mime) -> Option<char> {
+    Some(match mime {
+        Mime::Elf => '\u{f013}',
+        Mime::Png => '\u{f1c5}',
+        Mime::Gzip | Mime::Zip => '\u{f410}',
+        Mime::Pdf => '\u{f1c1}',
+        Mime::Script => '\u{f489}',
+        Mime::Text | Mime::Binary | Mime::Empty => return None,
+    })
+}
+
+fn file_name_icon(name: &str) -> Option<char> {
+    Some(match name {
+        "Cargo.toml" | "Cargo.lock" => '\u{e7a8}',
+        "Dockerfile" | "docker-compose.yml" | "docker-compose.yaml" | ".dockerignore" => '\u{f308}',
+        ".gitignore" | ".gitattributes" | ".gitmodules" | ".gitconfig" => '\u{f1d3}',
+        "Makefile" | "makefile" | "CMakeLists.txt" => '\u{e779}',
+        "LICENSE" | "LICENCE" | "COPYING" => '\u{f02d}',
+        "README" | "README.md" => '\u{f48a}',
+        "package.json" | "package-lock.json" => '\u{e71e}',
+        ".bashrc" | ".zshrc" | ".profile" | ".bash_profile" => '\u{f489}',
+        _ => return None,
+    })
+}
+
+fn extension_icon(extension: &str) -> Option<char> {
+    Some(match extension {
+        "rs" => '\u{e7a8}',
+        "toml" => '\u{e6b2}',
+        "md" | "markdown" => '\u{f48a}',
+        "json" | "jsonc" | "json5" => '\u{e60b}',
+        "yml" | "yaml" => '\u{e6a8}',
+        "ini" | "cfg" | "conf" | "config" => '\u{e615}',
+        "txt" | "log" => '\u{f15c}',
+        "lock" => '\u{f023}',
+        "c" | "h" => '\u{e61e}',
+        "cpp" | "cc" | "cxx" | "hpp" => '\u{e61d}',
+        "go" => '\u{e626}',
+        "py" | "pyc" => '\u{e606}',
+        "js" | "mjs" | "cjs" => '\u{e74e}',
+        "ts" | "tsx" => '\u{e628}',
+        "java" | "jar" => '\u{e738}',
+        "html" | "htm" => '\u{f13b}',
+        "css" | "scss" | "sass" => '\u{e749}',
+        "sh" | "bash" | "zsh" | "fish" => '\u{f489}',
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "ico" | "svg" | "webp" | "tiff" => '\u{f1c5}',
+        "mp3" | "flac" | "wav" | "ogg" | "m4a" => '\u{f001}',
+        "mp4" | "mkv" | "webm" | "avi" | "mov" => '\u{f03d}',
+        "zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar" => '\u{f410}',
+        "pdf" => '\u{f1c1}',
+        "deb" | "rpm" => '\u{e77d}',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        icons::{file_icon, folder_icon, IconSet, DEFAULT_FILE, DEFAULT_FOLDER},
+        mime::Mime,
+    };
+
+    #[test]
+    fn test_file_icon_by_extension() {
+        assert_eq!(file_icon("main.rs"), '\u{e7a8}');
+        assert_eq!(file_icon("photo.JPG"), '\u{f1c5}');
+        assert_eq!(file_icon("backup.tar.gz"), '\u{f410}');
+    }
+
+    #[test]
+    fn test_file_icon_by_name() {
+        assert_eq!(file_icon("Dockerfile"), '\u{f308}');
+        assert_eq!(file_icon(".gitignore"), '\u{f1d3}');
+    }
+
+    #[test]
+    fn test_file_icon_default() {
+        assert_eq!(file_icon("no_extension"), DEFAULT_FILE);
+        assert_eq!(file_icon("file.unknown"), DEFAULT_FILE);
+    }
+
+    #[test]
+    fn test_folder_icon() {
+        assert_eq!(folder_icon(".git"), '\u{e5fb}');
+        assert_eq!(folder_icon("node_modules"), '\u{e5fa}');
+        assert_eq!(folder_icon("some_folder"), DEFAULT_FOLDER);
+    }
+
+    #[test]
+    fn test_icon_set_fallbacks() {
+        assert_eq!(IconSet::Nerd.file("main.rs"), file_icon("main.rs"));
+        assert_eq!(IconSet::Unicode.folder(".git"), '\u{25b8}');
+        assert_eq!(IconSet::Ascii.file("main.rs"), '-');
+        assert_eq!(IconSet::Ascii.folder("src"), '+');
+        assert_eq!(IconSet::Ascii.locked(), '!');
+    }
+
+    #[test]
+    fn test_content_icon() {
+        assert_eq!(IconSet::Nerd.content(Mime::Png), Some('\u{f1c5}'));
+        assert_eq!(IconSet::Nerd.content(Mime::Gzip), file_icon("x.gz").into());
+        assert_eq!(IconSet::Nerd.content(Mime::Text), None);
+        assert_eq!(IconSet::Ascii.content(Mime::Png), None);
+    }
+}
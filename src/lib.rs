@@ -0,0 +1,45 @@
+#![warn(
+    clippy::nursery,
+    clippy::pedantic,
+    clippy::unwrap_or_default,
+    clippy::unwrap_used
+)]
+
+//! The listing logic of `sw`, usable without a terminal
+//!
+//! ```no_run
+//! use sw::{Lister, Sort};
+//!
+//! let entries = Lister::new(".").hidden(true).sort(Sort::Size).entries()?;
+//!
+//! for entry in entries {
+//!     let size = entry.stat.map_or(0, |stat| stat.size);
+//!     println!("{size} {}", entry.name.to_string_lossy());
+//! }
+//! # Ok::<(), sw::Error>(())
+//! ```
+
+pub mod column;
+pub mod config;
+pub mod dir;
+pub mod entry;
+pub mod error;
+pub mod filter;
+pub mod git;
+pub mod glob;
+pub mod grid;
+pub mod icons;
+pub mod lines;
+pub mod lister;
+pub mod mime;
+pub mod output;
+pub mod quote;
+pub mod summary;
+pub mod support;
+
+use std::result;
+
+pub use error::Error;
+pub use lister::{Lister, Sort};
+
+pub type Result<T> = result::Result<T, Error>;
@@ -0,0 +1,132 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    num::NonZeroUsize,
+    path::Path,
+    thread,
+};
+
+use crate::{entry::Entry, mime::Mime};
+
+/// How many bytes are read at once while counting
+const CHUNK_LENGTH: usize = 64 * 1024;
+
+/// Counts the lines of a text file, a last line without a line break counts too
+///
+/// Returns `None` for binaries, told by their first bytes or a NUL byte further on
+///
+/// # Errors
+/// Will return an error if the file cannot be opened or read
+#[allow(clippy::naive_bytecount)]
+pub fn count(path: &Path) -> io::Result<Option<u64>> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0; CHUNK_LENGTH];
+    let mut lines = 0;
+    let mut last = b'\n';
+    let mut first = true;
+
+    loop {
+        let read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let bytes = &buffer[..read];
+
+        if first && !matches!(Mime::sniff(bytes), Mime::Text | Mime::Script) {
+            return Ok(None);
+        }
+
+        if bytes.contains(&0) {
+            return Ok(None);
+        }
+
+        first = false;
+        lines += bytes.iter().filter(|byte| **byte == b'\n').count() as u64;
+        last = bytes[read - 1];
+    }
+
+    Ok(Some(lines + u64::from(last != b'\n')))
+}
+
+/// Counts the lines of the text files no larger than `max` bytes, spreading the
+/// entries over one thread per core
+pub fn count_all(entries: &mut [Entry], max: u64) {
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk = entries.len().div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        for entries in entries.chunks_mut(chunk) {
+            scope.spawn(move || {
+                for entry in entries {
+                    entry.count_lines(max);
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs};
+
+    use crate::{
+        entry::Entry,
+        lines::{count, count_all},
+    };
+
+    #[test]
+    fn test_count() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        let file = |name: &str, contents: &[u8]| {
+            let path = root.path().join(name);
+            fs::write(&path, contents).expect("Failed to write file");
+            path
+        };
+
+        assert_eq!(count(&file("a", b"one\ntwo\n")).ok(), Some(Some(2)));
+        assert_eq!(count(&file("b", b"one\ntwo")).ok(), Some(Some(2)));
+        assert_eq!(count(&file("c", b"")).ok(), Some(Some(0)));
+        assert_eq!(count(&file("d", b"#!/bin/sh\nexit\n")).ok(), Some(Some(2)));
+        assert_eq!(count(&file("e", b"\x7FELF\x02\x01")).ok(), Some(None));
+
+        let mut late_nul = b"line\n".repeat(20_000);
+        late_nul.push(0);
+        assert_eq!(count(&file("f", &late_nul)).ok(), Some(None));
+
+        assert_eq!(
+            count(&file("g", &b"line\n".repeat(30_000))).ok(),
+            Some(Some(30_000))
+        );
+        assert!(count(&root.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_count_all() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir(root.path().join("src")).expect("Failed to create directory");
+
+        let mut entries = ["src", "short", "long", "blob"]
+            .into_iter()
+            .map(|name| {
+                let path = root.path().join(name);
+                match name {
+                    "short" => fs::write(&path, b"a\nb\n").expect("Failed to write file"),
+                    "long" => fs::write(&path, b"a\n".repeat(100)).expect("Failed to write file"),
+                    "blob" => fs::write(&path, [0; 8]).expect("Failed to write file"),
+                    _ => {}
+                }
+
+                Entry::new(path, OsString::from(name), false).expect("Failed to read entry")
+            })
+            .collect::<Vec<_>>();
+
+        count_all(&mut entries, 100);
+
+        assert_eq!(
+            entries.iter().map(|entry| entry.lines).collect::<Vec<_>>(),
+            vec![None, Some(2), None, None]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use std::{
+    cmp::Reverse,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dir::{read_each, Directory, Hidden},
+    entry::Entry,
+    filter::Filter,
+    git::{GitStatus, Repository},
+    lines, Error, Result,
+};
+
+/// Order of the entries returned by [`Lister::entries`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Sort {
+    /// Folders before files, hidden ones first, each group by name
+    #[default]
+    Name,
+    /// Largest first
+    Size,
+    /// Most recently modified first
+    Modified,
+}
+
+/// Lists a directory the way the `sw` command does
+///
+/// ```no_run
+/// use sw::{Lister, Sort};
+///
+/// let directory = Lister::new("/tmp").hidden(true).directory()?;
+/// let largest = Lister::new("/tmp").sort(Sort::Size).entries()?;
+/// # Ok::<(), sw::Error>(())
+/// ```
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Clone, Debug)]
+pub struct Lister {
+    path: PathBuf,
+    hidden: Hidden,
+    filter: Filter,
+    git: bool,
+    git_ignore: bool,
+    xattrs: bool,
+    mime: bool,
+    lines: Option<u64>,
+    sort: Sort,
+}
+
+impl Lister {
+    #[must_use]
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            hidden: Hidden::Skip,
+            filter: Filter::default(),
+            git: false,
+            git_ignore: false,
+            xattrs: false,
+            mime: false,
+            lines: None,
+            sort: Sort::default(),
+        }
+    }
+
+    /// Which hidden entries to list, `true` lists them without `.` and `..`
+    #[must_use]
+    pub fn hidden(mut self, hidden: impl Into<Hidden>) -> Self {
+        self.hidden = hidden.into();
+        self
+    }
+
+    #[must_use]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Loads the git status of the entries when the directory is in a repository
+    #[must_use]
+    pub const fn git(mut self, git: bool) -> Self {
+        self.git = git;
+        self
+    }
+
+    /// Leaves out the entries ignored by git
+    #[must_use]
+    pub const fn git_ignore(mut self, git_ignore: bool) -> Self {
+        self.git_ignore = git_ignore;
+        self
+    }
+
+    /// Reads the extended attributes and the security context of the entries
+    #[must_use]
+    pub const fn xattrs(mut self, xattrs: bool) -> Self {
+        self.xattrs = xattrs;
+        self
+    }
+
+    /// Reads the first bytes of the regular files to tell their type
+    #[must_use]
+    pub const fn mime(mut self, mime: bool) -> Self {
+        self.mime = mime;
+        self
+    }
+
+    /// Counts the lines of the text files no larger than the given number of bytes,
+    /// in parallel
+    #[must_use]
+    pub const fn lines(mut self, max: Option<u64>) -> Self {
+        self.lines = max;
+        self
+    }
+
+    #[must_use]
+    pub const fn sort(mut self, sort: Sort) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// The repository holding the directory when git is asked for, and the
+    /// filter along with the ignore rules of the repository
+    fn repository(&self) -> (Option<Repository>, Filter) {
+        let repository = if self.git || self.git_ignore {
+            Repository::discover(&self.path)
+        } else {
+            None
+        };
+
+        let mut filter = self.filter.clone();
+
+        if self.git_ignore {
+            filter.git_ignore = repository
+                .as_ref()
+                .map(|repository| repository.ignore(&self.path));
+        }
+
+        (repository, filter)
+    }
+
+    /// # Errors
+    /// Will return an error if the directory cannot be read
+    pub fn directory(&self) -> Result<Directory> {
+        let (repository, filter) = self.repository();
+
+        let mut directory = Directory::from(&self.path, self.hidden, &filter)?;
+
+        if self.xattrs {
+            directory
+                .dots
+                .iter_mut()
+                .chain(&mut directory.entries)
+                .for_each(Entry::read_xattrs);
+        }
+
+        if self.mime {
+            directory.entries.iter_mut().for_each(Entry::sniff);
+        }
+
+        if let Some(max) = self.lines {
+            lines::count_all(&mut directory.entries, max);
+        }
+
+        if self.git {
+            directory.git = repository.and_then(|repository| repository.statuses(&self.path).ok());
+        }
+
+        Ok(directory)
+    }
+
+    /// Hands every listed entry and its git status to `each` as soon as it is read
+    ///
+    /// The entries come in the order the file system yields them, without `.` and
+    /// `..`, and their lines are counted one file after the other. Returns why
+    /// some entries could not be read
+    ///
+    /// # Errors
+    /// Will return an error if the directory cannot be read, or the first error of `each`
+    pub fn for_each(
+        &self,
+        mut each: impl FnMut(&Entry, Option<GitStatus>) -> Result<()>,
+    ) -> Result<Vec<Error>> {
+        let (repository, filter) = self.repository();
+
+        let statuses = repository
+            .filter(|_| self.git)
+            .and_then(|repository| repository.statuses(&self.path).ok());
+
+        let mut errors = Vec::new();
+
+        read_each(
+            &self.path,
+            self.hidden,
+            &filter,
+            &mut errors,
+            |mut entry| {
+                if self.xattrs {
+                    entry.read_xattrs();
+                }
+
+                if self.mime {
+                    entry.sniff();
+                }
+
+                if let Some(max) = self.lines {
+                    entry.count_lines(max);
+                }
+
+                let status = statuses.as_ref().map(|statuses| statuses.get(&entry.name));
+
+                each(&entry, status)
+            },
+        )?;
+
+        Ok(errors)
+    }
+
+    /// Every listed entry with its raw metadata, in the chosen order
+    ///
+    /// # Errors
+    /// Will return an error if the directory cannot be read
+    pub fn entries(&self) -> Result<Vec<Entry>> {
+        let mut entries = self.directory()?.entries;
+
+        match self.sort {
+            Sort::Name => {}
+            Sort::Size => entries.sort_by_key(|entry| Reverse(entry.stat.map(|stat| stat.size))),
+            Sort::Modified => {
+                entries.sort_by_key(|entry| Reverse(entry.stat.and_then(|stat| stat.modified)));
+            }
+        }
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs};
+
+    use crate::{
+        dir::Hidden,
+        filter::Filter,
+        glob::Pattern,
+        lister::{Lister, Sort},
+        mime::Mime,
+    };
+
+    fn fixture() -> tempfile::TempDir {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::create_dir(root.path().join("src")).expect("Failed to create directory");
+        fs::write(root.path().join("small.txt"), b"a").expect("Failed to write file");
+        fs::write(root.path().join("large.txt"), vec![0; 100_000]).expect("Failed to write file");
+        fs::write(root.path().join(".env"), b"KEY=1").expect("Failed to write file");
+
+        root
+    }
+
+    fn names(lister: &Lister) -> Vec<OsString> {
+        lister
+            .entries()
+            .expect("Failed to list")
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_entries() {
+        let root = fixture();
+
+        assert_eq!(
+            names(&Lister::new(root.path())),
+            vec!["src", "large.txt", "small.txt"]
+        );
+        assert_eq!(
+            names(&Lister::new(root.path()).hidden(true)),
+            vec!["src", ".env", "large.txt", "small.txt"]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_size() {
+        let root = fixture();
+
+        let entries = Lister::new(root.path())
+            .sort(Sort::Size)
+            .entries()
+            .expect("Failed to list");
+
+        assert_eq!(entries[0].name, "large.txt");
+        assert_eq!(entries[0].stat.map(|stat| stat.size), Some(100_000));
+    }
+
+    #[test]
+    fn test_mime() {
+        let root = fixture();
+
+        let mimes = |lister: Lister| {
+            lister
+                .entries()
+                .expect("Failed to list")
+                .into_iter()
+                .map(|entry| entry.mime)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(mimes(Lister::new(root.path())), vec![None, None, None]);
+        assert_eq!(
+            mimes(Lister::new(root.path()).mime(true)),
+            vec![None, Some(Mime::Binary), Some(Mime::Text)]
+        );
+    }
+
+    #[test]
+    fn test_xattrs() {
+        let root = fixture();
+
+        if xattr::set(root.path().join("small.txt"), "user.origin", b"test").is_err() {
+            // The filesystem of the temporary directory has no user attributes
+            return;
+        }
+
+        let xattrs = |lister: Lister| {
+            lister
+                .filter(Filter {
+                    only: vec![Pattern::new("small.txt")],
+                    ..Filter::default()
+                })
+                .entries()
+                .expect("Failed to list")
+                .remove(0)
+                .xattrs
+        };
+
+        assert!(xattrs(Lister::new(root.path())).is_empty());
+        assert_eq!(
+            xattrs(Lister::new(root.path()).xattrs(true)),
+            vec![OsString::from("user.origin")]
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let root = fixture();
+
+        let lines = Lister::new(root.path())
+            .lines(Some(1024))
+            .entries()
+            .expect("Failed to list")
+            .into_iter()
+            .map(|entry| entry.lines)
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec![None, None, Some(1)]);
+    }
+
+    #[test]
+    fn test_filter_and_dots() {
+        let root = fixture();
+
+        let lister = Lister::new(root.path()).filter(Filter {
+            only: vec![Pattern::new("*.txt")],
+            ..Filter::default()
+        });
+
+        assert_eq!(names(&lister), vec!["large.txt", "small.txt"]);
+
+        let directory = Lister::new(root.path())
+            .hidden(Hidden::All)
+            .directory()
+            .expect("Failed to list");
+
+        assert_eq!(directory.dots.len(), 2);
+        assert_eq!(
+            directory
+                .entries
+                .iter()
+                .filter(|entry| entry.hidden)
+                .count(),
+            1
+        );
+    }
+}
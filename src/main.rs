@@ -1,67 +1,206 @@
 #![warn(
     clippy::nursery,
     clippy::pedantic,
-    clippy::unwrap_or_else_default,
+    clippy::unwrap_or_default,
     clippy::unwrap_used
 )]
 
-pub mod args;
-pub mod dir;
-pub mod file;
-pub mod folder;
-pub mod support;
+mod args;
 
-use std::{env, error, fs, io, result};
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    time::SystemTime,
+};
 
 use args::Cli;
 use clap::Parser;
-use dir::Directory;
+use sw::{
+    column::Column,
+    config::Config,
+    dir::{stream_ndjson, DisplayOptions, Hidden},
+    entry::Indicator,
+    filter::Filter,
+    grid::{Direction, Terminal},
+    output::{Field, Format},
+    quote::QuotingStyle,
+    Error, Lister, Result,
+};
 
-pub type Error = Box<dyn error::Error>;
-pub type Result<T> = result::Result<T, Error>;
+/// Width used when the output is not a terminal
+const DEFAULT_WIDTH: usize = 80;
 
-fn main() -> Result<()> {
+/// The directory to list, the current one when no path is given
+fn resolve_path(path: Option<PathBuf>) -> Result<PathBuf> {
+    let path = path.unwrap_or_else(|| PathBuf::from("."));
+
+    fs::canonicalize(&path).map_err(|source| Error::Access { path, source })
+}
+
+/// Reads the extended attributes, file types and line counts only when the
+/// output shows them, none of them come with the metadata
+fn read_shown(lister: Lister, options: &DisplayOptions, lines_max: u64) -> Lister {
+    lister
+        .xattrs(
+            options.lists(Column::Perms)
+                || options.xattr
+                || options.context
+                || options.writes(Field::Xattrs)
+                || options.writes(Field::Context),
+        )
+        .mime(
+            options.mime
+                || (options.list && options.columns.contains(&Column::Mime))
+                || options.fields.contains(&Field::Mime),
+        )
+        .lines(
+            (options.lists(Column::Lines)
+                || (options.writes(Field::Lines) && (options.lines || !options.fields.is_empty())))
+            .then_some(lines_max),
+        )
+}
+
+/// Lists the directory, warning about the entries that cannot be read
+///
+/// Exits like `ls`: 0 when everything was listed, 1 when some entries could
+/// not be read and 2 when the directory itself could not be listed. A reader
+/// closing the pipe early, like `head`, is not an error
+fn main() -> ExitCode {
+    match run() {
+        Ok(errors) => {
+            for error in &errors {
+                eprintln!("sw: {error}");
+            }
+
+            if errors.is_empty() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(1)
+            }
+        }
+        Err(error) if error.is_broken_pipe() => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("sw: {error}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the listing and returns the errors of the entries that could not be read
+fn run() -> Result<Vec<Error>> {
     let stdout = io::stdout();
     let mut handler = stdout.lock();
 
-    let Some((width, _)) = term_size::dimensions() else {
-        return Err(Error::from("Cannot get the terminal window size"));
-    };
-
     let args = Cli::parse();
 
-    let directory = if let Some(dirpath) = args.path {
-        if dirpath.is_file() {
-            return Err(Error::from(format!(
-                "The given argument is a file and not a directory: {}",
-                dirpath.to_str().map_or("-", |name| name)
-            )));
-        }
+    let terminal = stdout.is_terminal();
 
-        let path = match fs::canonicalize(dirpath) {
-            Ok(a) => a,
-            Err(e) => return Err(Error::from(e.to_string())),
-        };
+    let config = Config::load().map_err(Error::Config)?;
 
-        Directory::from(&path, args.all, args.list)
+    let columns = if !args.long_columns.is_empty() {
+        args.long_columns
+    } else if let Some(columns) = config.columns.filter(|columns| !columns.is_empty()) {
+        columns
     } else {
-        let Ok(current_dir) = env::current_dir() else {
-            return Err(Error::from("Cannot read given directory"));
-        };
+        Column::DEFAULT.to_vec()
+    };
 
-        Directory::from(&current_dir, args.all, args.list)
+    let options = DisplayOptions {
+        all: args.all || args.almost_all,
+        list: args.list,
+        icons: args.icons.enabled(terminal).then_some(args.icon_set),
+        direction: if args.across {
+            Direction::Across
+        } else {
+            Direction::Down
+        },
+        quoting: args.quoting_style.unwrap_or(if terminal {
+            QuotingStyle::ShellEscape
+        } else {
+            QuotingStyle::Literal
+        }),
+        format: args.format,
+        fields: args.fields,
+        columns,
+        header: args.header,
+        inode: args.inode,
+        device: args.device,
+        xattr: args.xattr,
+        context: args.context,
+        indicator: if args.classify {
+            Indicator::Classify
+        } else if args.slash {
+            Indicator::Slash
+        } else {
+            Indicator::None
+        },
+        mime: args.mime,
+        lines: args.lines,
+        summary: args.summary,
     };
 
-    match directory {
-        Ok(d) => {
-            let Ok(()) = d.display_output(&mut handler, width, args.all, args.list) else {
-                return Err(Error::from("Cannot print the output in stdout"));
-            };
-        }
-        Err(e) => {
-            return Err(Error::from(e.to_string()));
-        }
+    let path = resolve_path(args.path)?;
+
+    let filter = Filter {
+        ignore: args.ignore,
+        only: args.only,
+        ignore_backups: args.ignore_backups,
+        git_ignore: None,
+        hidden: args.hidden_pattern,
+        types: args.types,
+        larger: args.larger,
+        smaller: args.smaller,
+        newer: args
+            .newer
+            .and_then(|age| SystemTime::now().checked_sub(age)),
+        older: args
+            .older
+            .and_then(|age| SystemTime::now().checked_sub(age)),
+    };
+
+    let hidden = if args.all {
+        Hidden::All
+    } else if args.almost_all {
+        Hidden::AlmostAll
+    } else {
+        Hidden::Skip
+    };
+
+    let lister = Lister::new(&path)
+        .hidden(hidden)
+        .filter(filter)
+        .git(args.git)
+        .git_ignore(args.git_ignore);
+
+    let errors = print(
+        &mut handler,
+        &read_shown(lister, &options, args.lines_max),
+        &options,
+    )?;
+
+    handler.flush().map_err(Error::Write)?;
+
+    Ok(errors)
+}
+
+/// NDJSON is written while the directory is read, the other formats only once
+/// all of it is read and sorted
+fn print(out: &mut impl Write, lister: &Lister, options: &DisplayOptions) -> Result<Vec<Error>> {
+    if options.format == Format::Ndjson {
+        return stream_ndjson(out, lister, options);
     }
 
-    Ok(())
+    let directory = lister.directory()?;
+
+    directory.display_output(
+        out,
+        &Terminal {
+            fallback: DEFAULT_WIDTH,
+        },
+        options,
+    )?;
+
+    Ok(directory.errors)
 }
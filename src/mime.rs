@@ -0,0 +1,136 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// How many bytes are read from the start of a file to sniff its type
+const SNIFF_LENGTH: u64 = 1024;
+
+/// Signatures found at the start of files, checked in order
+const MAGIC: [(&[u8], Mime); 7] = [
+    (b"\x7FELF", Mime::Elf),
+    (b"\x89PNG\r\n\x1A\n", Mime::Png),
+    (b"\x1F\x8B", Mime::Gzip),
+    (b"PK\x03\x04", Mime::Zip),
+    (b"PK\x05\x06", Mime::Zip),
+    (b"%PDF-", Mime::Pdf),
+    (b"#!", Mime::Script),
+];
+
+/// The type of a regular file, told by its first bytes rather than its name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mime {
+    Elf,
+    Png,
+    Gzip,
+    Zip,
+    Pdf,
+    /// Starts with a `#!` line
+    Script,
+    /// Valid UTF-8 without NUL bytes
+    Text,
+    Binary,
+    Empty,
+}
+
+impl Mime {
+    /// Reads the start of the file at `path`
+    ///
+    /// # Errors
+    /// Will return an error if the file cannot be opened or read
+    pub fn of(path: &Path) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        File::open(path)?
+            .take(SNIFF_LENGTH)
+            .read_to_end(&mut bytes)?;
+
+        Ok(Self::sniff(&bytes))
+    }
+
+    /// Tells the type of a file from its first bytes
+    #[must_use]
+    pub fn sniff(bytes: &[u8]) -> Self {
+        if bytes.is_empty() {
+            return Self::Empty;
+        }
+
+        if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| bytes.starts_with(magic)) {
+            return *mime;
+        }
+
+        let text = match std::str::from_utf8(bytes) {
+            Ok(_) => true,
+            // The read may stop in the middle of a character
+            Err(error) => error.error_len().is_none(),
+        };
+
+        if text && !bytes.contains(&0) {
+            Self::Text
+        } else {
+            Self::Binary
+        }
+    }
+
+    /// The media type, as `file --mime-type` prints it
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Elf => "application/x-executable",
+            Self::Png => "image/png",
+            Self::Gzip => "application/gzip",
+            Self::Zip => "application/zip",
+            Self::Pdf => "application/pdf",
+            Self::Script => "text/x-script",
+            Self::Text => "text/plain",
+            Self::Binary => "application/octet-stream",
+            Self::Empty => "inode/x-empty",
+        }
+    }
+
+    /// Color of the name, `None` keeps the one picked by the entry type
+    #[must_use]
+    pub const fn color(self) -> Option<&'static str> {
+        match self {
+            Self::Png => Some("35"),
+            Self::Gzip | Self::Zip => Some("91"),
+            Self::Pdf => Some("36"),
+            Self::Elf | Self::Script | Self::Text | Self::Binary | Self::Empty => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::mime::Mime;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(Mime::sniff(b"\x7FELF\x02\x01\x01\0"), Mime::Elf);
+        assert_eq!(Mime::sniff(b"\x89PNG\r\n\x1A\n\0\0\0\rIHDR"), Mime::Png);
+        assert_eq!(Mime::sniff(b"\x1F\x8B\x08\0"), Mime::Gzip);
+        assert_eq!(Mime::sniff(b"PK\x03\x04\x14\0"), Mime::Zip);
+        assert_eq!(Mime::sniff(b"%PDF-1.7\n"), Mime::Pdf);
+        assert_eq!(Mime::sniff(b"#!/bin/sh\necho hi\n"), Mime::Script);
+        assert_eq!(Mime::sniff("caf\u{e9} au lait\n".as_bytes()), Mime::Text);
+        assert_eq!(Mime::sniff(b"caf\xC3"), Mime::Text);
+        assert_eq!(Mime::sniff(b"\0\x01\x02\x03"), Mime::Binary);
+        assert_eq!(Mime::sniff(b"\xFF\xFE\xFD text"), Mime::Binary);
+        assert_eq!(Mime::sniff(b""), Mime::Empty);
+    }
+
+    #[test]
+    fn test_of() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        let blob = root.path().join("blob");
+        let mut png = b"\x89PNG\r\n\x1A\n".to_vec();
+        png.resize(4096, 0);
+        fs::write(&blob, png).expect("Failed to write file");
+
+        assert_eq!(Mime::of(&blob).ok(), Some(Mime::Png));
+        assert!(Mime::of(&root.path().join("missing")).is_err());
+        assert_eq!(Mime::Png.as_str(), "image/png");
+    }
+}
@@ -0,0 +1,631 @@
+use std::{
+    ffi::OsStr,
+    io::{self, Write},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use uzers::{Groups, Users, UsersCache};
+
+use crate::{
+    entry::{Entry, Kind},
+    git::GitStatus,
+    summary::Summary,
+    support::format_permissions,
+};
+
+/// How the listing gets written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Styled text for people, the grid or the long list
+    #[default]
+    Text,
+    /// A single JSON document with every entry and its raw metadata
+    Json,
+    /// One JSON object per line and per entry, written while the directory is
+    /// read, in the order the file system yields the entries
+    Ndjson,
+    /// Comma separated values with a header row
+    Csv,
+    /// Tab separated values with a header row
+    Tsv,
+}
+
+/// A field of a [`Record`] that can be picked for the machine readable formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Field {
+    Name,
+    NameBytes,
+    Kind,
+    Hidden,
+    Size,
+    Mode,
+    Permissions,
+    Links,
+    Inode,
+    Device,
+    Uid,
+    User,
+    Gid,
+    Group,
+    Modified,
+    Accessed,
+    Created,
+    LinkTarget,
+    LinkTargetBytes,
+    Xattrs,
+    Context,
+    Mime,
+    Lines,
+    Git,
+}
+
+impl Field {
+    /// The key of the field in the serialized record
+    #[must_use]
+    pub const fn key(self) -> &'static str {
+        match self {
+            Self::Name => "name",
+            Self::NameBytes => "name_bytes",
+            Self::Kind => "kind",
+            Self::Hidden => "hidden",
+            Self::Size => "size",
+            Self::Mode => "mode",
+            Self::Permissions => "permissions",
+            Self::Links => "links",
+            Self::Inode => "inode",
+            Self::Device => "device",
+            Self::Uid => "uid",
+            Self::User => "user",
+            Self::Gid => "gid",
+            Self::Group => "group",
+            Self::Modified => "modified",
+            Self::Accessed => "accessed",
+            Self::Created => "created",
+            Self::LinkTarget => "link_target",
+            Self::LinkTargetBytes => "link_target_bytes",
+            Self::Xattrs => "xattrs",
+            Self::Context => "context",
+            Self::Mime => "mime",
+            Self::Lines => "lines",
+            Self::Git => "git",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Timestamp {
+    pub rfc3339: String,
+    /// Seconds since the Unix epoch
+    pub epoch: i64,
+}
+
+impl From<SystemTime> for Timestamp {
+    fn from(time: SystemTime) -> Self {
+        let time = DateTime::<Utc>::from(time);
+
+        Self {
+            rfc3339: time.to_rfc3339(),
+            epoch: time.timestamp(),
+        }
+    }
+}
+
+/// An entry with its metadata left unformatted, for output read by programs,
+/// the metadata is `None` when it could not be read
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Record {
+    /// The name, invalid UTF-8 replaced with U+FFFD
+    pub name: String,
+    /// The raw bytes of the name, only when it is not valid UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_bytes: Option<Vec<u8>>,
+    pub kind: Option<Kind>,
+    pub hidden: bool,
+    /// Size in bytes
+    pub size: Option<u64>,
+    pub mode: Option<u32>,
+    pub permissions: Option<String>,
+    pub links: Option<u64>,
+    pub inode: Option<u64>,
+    /// Id of the device holding the entry
+    pub device: Option<u64>,
+    pub uid: Option<u32>,
+    pub user: Option<String>,
+    pub gid: Option<u32>,
+    pub group: Option<String>,
+    pub modified: Option<Timestamp>,
+    pub accessed: Option<Timestamp>,
+    pub created: Option<Timestamp>,
+    /// Where the entry points to when it is a symbolic link
+    pub link_target: Option<String>,
+    /// The raw bytes of the link target, only when it is not valid UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_target_bytes: Option<Vec<u8>>,
+    /// Names of the extended attributes
+    pub xattrs: Vec<String>,
+    /// `SELinux` security context
+    pub context: Option<String>,
+    /// Media type of a regular file told by its first bytes, only when they were read
+    pub mime: Option<String>,
+    /// Line count of a text file, only when the lines were counted
+    pub lines: Option<u64>,
+    /// Staged and worktree change letters, only when git statuses were loaded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+}
+
+impl Record {
+    /// The owner and group names are looked up in `users`, or left out without it
+    #[must_use]
+    pub fn from(entry: &Entry, git: Option<GitStatus>, users: Option<&UsersCache>) -> Self {
+        let stat = entry.stat;
+
+        Self {
+            name: entry.name.to_string_lossy().into_owned(),
+            name_bytes: raw_bytes(&entry.name),
+            kind: entry.kind,
+            hidden: entry.hidden,
+            size: stat.map(|stat| stat.size),
+            mode: stat.map(|stat| stat.mode),
+            permissions: stat.map(|stat| format_permissions(stat.mode)),
+            links: stat.map(|stat| stat.links),
+            inode: stat.map(|stat| stat.inode),
+            device: stat.map(|stat| stat.device),
+            uid: stat.map(|stat| stat.uid),
+            user: stat
+                .zip(users)
+                .and_then(|(stat, users)| users.get_user_by_uid(stat.uid))
+                .map(|user| user.name().to_string_lossy().into_owned()),
+            gid: stat.map(|stat| stat.gid),
+            group: stat
+                .zip(users)
+                .and_then(|(stat, users)| users.get_group_by_gid(stat.gid))
+                .map(|group| group.name().to_string_lossy().into_owned()),
+            modified: stat.and_then(|stat| stat.modified).map(Timestamp::from),
+            accessed: stat.and_then(|stat| stat.accessed).map(Timestamp::from),
+            created: stat.and_then(|stat| stat.created).map(Timestamp::from),
+            link_target: entry
+                .link_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+            link_target_bytes: entry
+                .link_target
+                .as_ref()
+                .and_then(|target| raw_bytes(target.as_os_str())),
+            xattrs: entry
+                .xattrs
+                .iter()
+                .map(|name| name.to_string_lossy().into_owned())
+                .collect(),
+            context: entry.context.clone(),
+            mime: entry.mime.map(|mime| mime.as_str().to_string()),
+            lines: entry.lines,
+            git: git.map(|status| status.letters()),
+        }
+    }
+}
+
+/// The bytes of a name that would lose some of them as a string, two names
+/// differing only in their invalid UTF-8 must not come out the same
+fn raw_bytes(name: &OsStr) -> Option<Vec<u8>> {
+    name.to_str().is_none().then(|| name.as_bytes().to_vec())
+}
+
+/// The whole listing of a directory
+#[derive(Debug, Serialize)]
+pub struct Document {
+    pub path: String,
+    /// The raw bytes of the path, only when it is not valid UTF-8
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path_bytes: Option<Vec<u8>>,
+    pub entries: Vec<Map<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
+}
+
+/// Keeps only the given fields of the record, in that order, or all of them
+/// when none are given
+#[must_use]
+pub fn select(record: &Record, fields: &[Field]) -> Map<String, Value> {
+    let Ok(Value::Object(mut all)) = serde_json::to_value(record) else {
+        return Map::new();
+    };
+
+    if fields.is_empty() {
+        return all;
+    }
+
+    fields
+        .iter()
+        .filter_map(|field| {
+            all.remove(field.key())
+                .map(|value| (field.key().to_string(), value))
+        })
+        .collect()
+}
+
+/// Writes the records of the directory at `path` in one of the machine readable formats,
+/// the summary goes in the JSON document or on a last line of its own with NDJSON
+///
+/// NDJSON and the tables write each record as it comes, only the JSON document
+/// holds them all at once
+///
+/// # Errors
+/// Will return an error if the output cannot be written
+pub fn write_records<W: Write>(
+    out: &mut W,
+    format: Format,
+    path: &Path,
+    records: impl IntoIterator<Item = Record>,
+    fields: &[Field],
+    summary: Option<Summary>,
+) -> io::Result<()> {
+    let entries = records.into_iter().map(|record| select(&record, fields));
+
+    match format {
+        Format::Text => Ok(()),
+        Format::Json => {
+            let document = Document {
+                path: path.to_string_lossy().into_owned(),
+                path_bytes: raw_bytes(path.as_os_str()),
+                entries: entries.collect(),
+                summary,
+            };
+
+            serde_json::to_writer_pretty(&mut *out, &document)?;
+            writeln!(out)
+        }
+        Format::Ndjson => {
+            for entry in entries {
+                serde_json::to_writer(&mut *out, &entry)?;
+                writeln!(out)?;
+            }
+
+            summary.map_or(Ok(()), |summary| write_ndjson_summary(out, summary))
+        }
+        Format::Csv | Format::Tsv => {
+            let (separator, escape): (&str, fn(&str) -> String) = if format == Format::Csv {
+                (",", csv_escape)
+            } else {
+                ("\t", tsv_escape)
+            };
+
+            let header = if fields.is_empty() {
+                Field::value_variants()
+            } else {
+                fields
+            };
+
+            let header = header.iter().map(|field| field.key()).collect::<Vec<_>>();
+            writeln!(out, "{}", header.join(separator))?;
+
+            for entry in entries {
+                let row = header
+                    .iter()
+                    .map(|key| escape(&cell(entry.get(*key))))
+                    .collect::<Vec<_>>();
+
+                writeln!(out, "{}", row.join(separator))?;
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Writes one record as a line of NDJSON
+///
+/// # Errors
+/// Will return an error if the output cannot be written
+pub fn write_ndjson<W: Write>(out: &mut W, record: &Record, fields: &[Field]) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, &select(record, fields))?;
+    writeln!(out)
+}
+
+/// Writes the summary as the last line of NDJSON
+///
+/// # Errors
+/// Will return an error if the output cannot be written
+pub fn write_ndjson_summary<W: Write>(out: &mut W, summary: Summary) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, &serde_json::json!({ "summary": summary }))?;
+    writeln!(out)
+}
+
+/// A value as a single table cell, timestamps are written in RFC 3339 and
+/// lists separated by spaces
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| cell(Some(item)))
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(Value::Object(object)) => object
+            .get("rfc3339")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Quotes the cell when it holds a comma, a quote or a line break
+fn csv_escape(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Backslash escapes tabs and line breaks so every row stays on one line
+fn tsv_escape(cell: &str) -> String {
+    cell.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{OsStr, OsString},
+        fs,
+        path::Path,
+        time::SystemTime,
+    };
+
+    use crate::{
+        entry::{Entry, Kind},
+        output::{cell, csv_escape, tsv_escape, write_records, Field, Format, Record, Timestamp},
+        summary::Summary,
+    };
+
+    fn record(name: &str, size: u64) -> Record {
+        Record {
+            name: name.to_string(),
+            name_bytes: None,
+            kind: Some(Kind::File),
+            hidden: false,
+            size: Some(size),
+            mode: Some(0o100_644),
+            permissions: Some(String::from("-rw-r--r--")),
+            links: Some(1),
+            inode: Some(42),
+            device: Some(2049),
+            uid: Some(0),
+            user: Some(String::from("root")),
+            gid: Some(0),
+            group: None,
+            modified: Some(Timestamp::from(SystemTime::UNIX_EPOCH)),
+            accessed: None,
+            created: None,
+            link_target: None,
+            link_target_bytes: None,
+            xattrs: Vec::new(),
+            context: None,
+            mime: None,
+            lines: None,
+            git: None,
+        }
+    }
+
+    fn written(format: Format, fields: &[Field]) -> String {
+        let mut out = Vec::new();
+        let records = [record("a.txt", 1), record("b, \"c\".txt", 2)];
+
+        write_records(&mut out, format, Path::new("/tmp"), records, fields, None)
+            .expect("Failed to write records");
+
+        String::from_utf8(out).expect("Output is not UTF-8")
+    }
+
+    #[test]
+    fn test_timestamp() {
+        let timestamp = Timestamp::from(SystemTime::UNIX_EPOCH);
+
+        assert_eq!(timestamp.epoch, 0);
+        assert_eq!(timestamp.rfc3339, "1970-01-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_record() {
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        fs::write(root.path().join("notes.txt"), b"hello").expect("Failed to write file");
+        std::os::unix::fs::symlink("notes.txt", root.path().join("link")).expect("Failed to link");
+
+        let entry = |name: &str| {
+            Entry::new(root.path().join(name), OsString::from(name), false)
+                .expect("Failed to read entry")
+        };
+
+        let record = Record::from(&entry("notes.txt"), None, None);
+
+        assert_eq!(record.kind, Some(Kind::File));
+        assert_eq!(record.size, Some(5));
+        assert_eq!(record.links, Some(1));
+        assert_eq!(record.link_target, None);
+        assert!(record.modified.is_some());
+
+        let link = Record::from(&entry("link"), None, None);
+        let unreadable = Record::from(
+            &Entry::unreadable(root.path().join("x"), OsString::from("x"), false),
+            None,
+            None,
+        );
+
+        assert_eq!(unreadable.name, "x");
+        assert_eq!(unreadable.kind, None);
+        assert_eq!(unreadable.size, None);
+        assert_eq!(unreadable.permissions, None);
+        assert_eq!(
+            serde_json::to_value(&unreadable).expect("Failed to serialize")["mode"],
+            serde_json::Value::Null
+        );
+
+        assert_eq!(link.kind, Some(Kind::Symlink));
+        assert_eq!(link.link_target.as_deref(), Some("notes.txt"));
+
+        let mut out = Vec::new();
+        write_records(
+            &mut out,
+            Format::Json,
+            Path::new("/tmp"),
+            [record],
+            &[],
+            None,
+        )
+        .expect("Failed to write records");
+        let json: serde_json::Value = serde_json::from_slice(&out).expect("Invalid JSON");
+
+        assert_eq!(json["path"], "/tmp");
+        assert_eq!(json["entries"][0]["name"], "notes.txt");
+        assert_eq!(json["entries"][0]["kind"], "file");
+        assert_eq!(json["entries"][0]["size"], 5);
+        assert!(json["entries"][0]["modified"]["epoch"].is_i64());
+        assert!(json["entries"][0].get("git").is_none());
+    }
+
+    #[test]
+    fn test_record_invalid_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = tempfile::tempdir().expect("Failed to create temporary directory");
+        let names = [OsStr::from_bytes(b"a\xff"), OsStr::from_bytes(b"a\xfe")];
+
+        for name in names {
+            fs::write(root.path().join(name), b"").expect("Failed to write file");
+        }
+        std::os::unix::fs::symlink(names[0], root.path().join("link")).expect("Failed to link");
+
+        let record = |name: &OsStr| {
+            Record::from(
+                &Entry::new(root.path().join(name), name.to_os_string(), false)
+                    .expect("Failed to read entry"),
+                None,
+                None,
+            )
+        };
+
+        let (first, second) = (record(names[0]), record(names[1]));
+
+        assert_eq!(first.name, second.name);
+        assert_eq!(first.name_bytes, Some(b"a\xff".to_vec()));
+        assert_eq!(second.name_bytes, Some(b"a\xfe".to_vec()));
+
+        let link = record(OsStr::new("link"));
+
+        assert_eq!(link.name_bytes, None);
+        assert_eq!(link.link_target_bytes, Some(b"a\xff".to_vec()));
+
+        let json = serde_json::to_value(&first).expect("Failed to serialize");
+
+        assert_eq!(json["name_bytes"], serde_json::json!([97, 255]));
+        assert!(serde_json::to_value(&link)
+            .expect("Failed to serialize")
+            .get("name_bytes")
+            .is_none());
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let output = written(Format::Ndjson, &[Field::Size, Field::Name]);
+
+        assert_eq!(
+            output,
+            "{\"size\":1,\"name\":\"a.txt\"}\n{\"size\":2,\"name\":\"b, \\\"c\\\".txt\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let output = written(
+            Format::Csv,
+            &[Field::Name, Field::Size, Field::Group, Field::Modified],
+        );
+
+        assert_eq!(
+            output,
+            "name,size,group,modified\n\
+             a.txt,1,,1970-01-01T00:00:00+00:00\n\
+             \"b, \"\"c\"\".txt\",2,,1970-01-01T00:00:00+00:00\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv_all_fields() {
+        let output = written(Format::Tsv, &[]);
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("name\tname_bytes\tkind\thidden\tsize\t"));
+        assert!(lines[0]
+            .ends_with("\tlink_target\tlink_target_bytes\txattrs\tcontext\tmime\tlines\tgit"));
+        assert!(lines[1].starts_with("a.txt\t\tfile\tfalse\t1\t33188\t"));
+    }
+
+    #[test]
+    fn test_cell() {
+        assert_eq!(
+            cell(Some(&serde_json::json!(["user.a", "user.b"]))),
+            "user.a user.b"
+        );
+        assert_eq!(cell(Some(&serde_json::json!([]))), "");
+        assert_eq!(cell(None), "");
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(csv_escape("plain"), "plain");
+        assert_eq!(csv_escape("a\nb"), "\"a\nb\"");
+        assert_eq!(tsv_escape("a\tb\\"), "a\\tb\\\\");
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = Summary {
+            folders: 1,
+            files: 2,
+            hidden: 0,
+            size: 3,
+        };
+
+        let mut out = Vec::new();
+        write_records(
+            &mut out,
+            Format::Ndjson,
+            Path::new("/tmp"),
+            [],
+            &[],
+            Some(summary),
+        )
+        .expect("Failed to write records");
+
+        assert_eq!(
+            String::from_utf8_lossy(&out),
+            "{\"summary\":{\"folders\":1,\"files\":2,\"hidden\":0,\"size\":3}}\n"
+        );
+
+        let mut out = Vec::new();
+        write_records(
+            &mut out,
+            Format::Json,
+            Path::new("/tmp"),
+            [],
+            &[],
+            Some(summary),
+        )
+        .expect("Failed to write records");
+        let json: serde_json::Value = serde_json::from_slice(&out).expect("Invalid JSON");
+
+        assert_eq!(json["summary"]["files"], 2);
+    }
+}
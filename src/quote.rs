@@ -0,0 +1,196 @@
+use std::{ffi::OsStr, fmt::Write, os::unix::ffi::OsStrExt};
+
+use clap::ValueEnum;
+
+/// Characters that make a name need quoting before it can be pasted into a shell
+const SHELL_SPECIAL: &str = " \t!\"#$&'()*;<=>?[\\]^`{|}~";
+
+/// How names are written out, mirrors the quoting styles of ls
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum QuotingStyle {
+    /// Print the name as it is
+    Literal,
+    /// Quote the name for a shell, unprintable characters are shown as ?
+    Shell,
+    /// Quote the name for a shell, unprintable characters are escaped with $''
+    ShellEscape,
+    /// Quote the name like a C string
+    C,
+    /// Escape the name like a C string without the quotes
+    Escape,
+}
+
+/// A run of the name that is either printable text or raw bytes that have
+/// to be escaped (control characters and invalid UTF-8)
+enum Piece<'a> {
+    Text(&'a str),
+    Raw(&'a [u8]),
+}
+
+fn pieces(name: &OsStr) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+
+    for chunk in name.as_bytes().utf8_chunks() {
+        let mut rest = chunk.valid();
+
+        while let Some(start) = rest.find(char::is_control) {
+            let end = rest[start..]
+                .find(|character: char| !character.is_control())
+                .map_or(rest.len(), |offset| start + offset);
+
+            if start > 0 {
+                pieces.push(Piece::Text(&rest[..start]));
+            }
+            pieces.push(Piece::Raw(&rest.as_bytes()[start..end]));
+
+            rest = &rest[end..];
+        }
+
+        if !rest.is_empty() {
+            pieces.push(Piece::Text(rest));
+        }
+
+        if !chunk.invalid().is_empty() {
+            pieces.push(Piece::Raw(chunk.invalid()));
+        }
+    }
+
+    pieces
+}
+
+#[must_use]
+pub fn quote(name: &OsStr, style: QuotingStyle) -> String {
+    match style {
+        QuotingStyle::Literal => name.to_string_lossy().into_owned(),
+        QuotingStyle::Shell => shell(name, false),
+        QuotingStyle::ShellEscape => shell(name, true),
+        QuotingStyle::C => format!("\"{}\"", c_escape(name, false)),
+        QuotingStyle::Escape => c_escape(name, true),
+    }
+}
+
+fn shell(name: &OsStr, escape: bool) -> String {
+    let pieces = pieces(name);
+
+    let raw = pieces.iter().any(|piece| matches!(piece, Piece::Raw(_)));
+
+    let special = pieces.iter().any(|piece| match piece {
+        Piece::Text(text) => text.contains(|character| SHELL_SPECIAL.contains(character)),
+        Piece::Raw(_) => false,
+    });
+
+    if !escape || !raw {
+        let text = pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => (*text).to_string(),
+                Piece::Raw(bytes) => "?".repeat(String::from_utf8_lossy(bytes).chars().count()),
+            })
+            .collect::<String>();
+
+        return if special || raw || text.is_empty() {
+            single_quote(&text)
+        } else {
+            text
+        };
+    }
+
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => single_quote(text),
+            Piece::Raw(bytes) => format!("$'{}'", escape_bytes(bytes)),
+        })
+        .collect()
+}
+
+fn single_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn c_escape(name: &OsStr, escape_spaces: bool) -> String {
+    pieces(name)
+        .iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text
+                .chars()
+                .map(|character| match character {
+                    '\\' => String::from("\\\\"),
+                    '"' if !escape_spaces => String::from("\\\""),
+                    ' ' if escape_spaces => String::from("\\ "),
+                    _ => character.to_string(),
+                })
+                .collect(),
+            Piece::Raw(bytes) => escape_bytes(bytes),
+        })
+        .collect()
+}
+
+fn escape_bytes(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut escaped, byte| {
+        match byte {
+            0x07 => escaped.push_str("\\a"),
+            0x08 => escaped.push_str("\\b"),
+            b'\t' => escaped.push_str("\\t"),
+            b'\n' => escaped.push_str("\\n"),
+            0x0B => escaped.push_str("\\v"),
+            0x0C => escaped.push_str("\\f"),
+            b'\r' => escaped.push_str("\\r"),
+            _ => {
+                let _ = write!(escaped, "\\{byte:03o}");
+            }
+        }
+
+        escaped
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    use crate::quote::{quote, QuotingStyle};
+
+    fn name(bytes: &[u8]) -> &OsStr {
+        OsStr::from_bytes(bytes)
+    }
+
+    #[test]
+    fn test_quote_literal() {
+        assert_eq!(quote(name(b"a b"), QuotingStyle::Literal), "a b");
+        assert_eq!(quote(name(b"a\xffb"), QuotingStyle::Literal), "a\u{fffd}b");
+    }
+
+    #[test]
+    fn test_quote_shell() {
+        assert_eq!(quote(name(b"plain.txt"), QuotingStyle::Shell), "plain.txt");
+        assert_eq!(quote(name(b"a b"), QuotingStyle::Shell), "'a b'");
+        assert_eq!(quote(name(b"it's"), QuotingStyle::Shell), "'it'\\''s'");
+        assert_eq!(quote(name(b"a\nb"), QuotingStyle::Shell), "'a?b'");
+    }
+
+    #[test]
+    fn test_quote_shell_escape() {
+        assert_eq!(quote(name(b"a b"), QuotingStyle::ShellEscape), "'a b'");
+        assert_eq!(
+            quote(name(b"a\nb"), QuotingStyle::ShellEscape),
+            "'a'$'\\n''b'"
+        );
+        assert_eq!(quote(name(b"\xff"), QuotingStyle::ShellEscape), "$'\\377'");
+    }
+
+    #[test]
+    fn test_quote_c() {
+        assert_eq!(quote(name(b"a\"b"), QuotingStyle::C), "\"a\\\"b\"");
+        assert_eq!(quote(name(b"a\tb\xfe"), QuotingStyle::C), "\"a\\tb\\376\"");
+    }
+
+    #[test]
+    fn test_quote_escape() {
+        assert_eq!(quote(name(b"a b\n"), QuotingStyle::Escape), "a\\ b\\n");
+        assert_eq!(
+            quote(name(b"back\\slash"), QuotingStyle::Escape),
+            "back\\\\slash"
+        );
+    }
+}
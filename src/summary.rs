@@ -0,0 +1,116 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::entry::Entry;
+
+const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Counts and total size of the listed entries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub folders: usize,
+    pub files: usize,
+    /// Hidden folders and files, also counted in `folders` and `files`
+    pub hidden: usize,
+    /// Size of the files in bytes
+    pub size: u64,
+}
+
+impl Summary {
+    /// Counts the entry, adding its size when it is a file
+    pub fn add(&mut self, entry: &Entry) {
+        if entry.folder {
+            self.folders += 1;
+        } else {
+            self.files += 1;
+            self.size += entry.stat.map_or(0, |stat| stat.size);
+        }
+
+        if entry.hidden {
+            self.hidden += 1;
+        }
+    }
+}
+
+/// A size in bytes with one decimal in the largest unit that keeps it above one
+#[must_use]
+pub fn format_total(size: u64) -> String {
+    if size < 1024 {
+        return format!("{size} B");
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = size as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+fn plural(count: usize, word: &str) -> String {
+    if count == 1 {
+        format!("{count} {word}")
+    } else {
+        format!("{count} {word}s")
+    }
+}
+
+/// Written like `12 folders, 48 files (3 hidden), 1.2 GiB total`
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}, {}",
+            plural(self.folders, "folder"),
+            plural(self.files, "file")
+        )?;
+
+        if self.hidden > 0 {
+            write!(f, " ({} hidden)", self.hidden)?;
+        }
+
+        write!(f, ", {} total", format_total(self.size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::summary::{format_total, Summary};
+
+    #[test]
+    fn test_format_total() {
+        assert_eq!(format_total(0), "0 B");
+        assert_eq!(format_total(1023), "1023 B");
+        assert_eq!(format_total(1536), "1.5 KiB");
+        assert_eq!(format_total(1_288_490_189), "1.2 GiB");
+    }
+
+    #[test]
+    fn test_display() {
+        let summary = Summary {
+            folders: 12,
+            files: 48,
+            hidden: 3,
+            size: 1_288_490_189,
+        };
+
+        assert_eq!(
+            summary.to_string(),
+            "12 folders, 48 files (3 hidden), 1.2 GiB total"
+        );
+
+        let summary = Summary {
+            folders: 1,
+            files: 1,
+            hidden: 0,
+            size: 10,
+        };
+
+        assert_eq!(summary.to_string(), "1 folder, 1 file, 10 B total");
+    }
+}
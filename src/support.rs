@@ -1,22 +1,38 @@
-use std::{fs::Metadata, os::unix::prelude::PermissionsExt, path::Path};
+use std::{
+    ffi::{OsStr, OsString},
+    os::unix::ffi::OsStrExt,
+    path::Path,
+    time::SystemTime,
+};
 
 use chrono::{DateTime, Datelike, Timelike, Utc};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+const SIZE_HELPER: [char; 6] = ['b', 'k', 'm', 'g', 't', 'p'];
 
 const DATE_MAPPER: [&str; 13] = [
     "-", "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dev",
 ];
 
+/// The type letter followed by the three permission triplets, like `drwxr-xr-x`
 #[must_use]
-pub fn parse_permissions(metadata: &Metadata) -> String {
+pub fn format_permissions(mode: u32) -> String {
+    let kind = match mode & 0o170_000 {
+        0o040_000 => "d",
+        0o120_000 => "l",
+        0o010_000 => "p",
+        0o140_000 => "s",
+        0o060_000 => "b",
+        0o020_000 => "c",
+        _ => "-",
+    };
+
     [
-        if metadata.is_dir() {
-            String::from("d")
-        } else {
-            String::from("-")
-        },
-        triplet(metadata.permissions().mode(), 256, 128, 64),
-        triplet(metadata.permissions().mode(), 32, 16, 8),
-        triplet(metadata.permissions().mode(), 4, 2, 1),
+        String::from(kind),
+        triplet(mode, 256, 128, 64),
+        triplet(mode, 32, 16, 8),
+        triplet(mode, 4, 2, 1),
     ]
     .join("")
 }
@@ -35,58 +51,57 @@ fn triplet(mode: u32, read: u32, write: u32, execute: u32) -> String {
 }
 
 #[must_use]
-pub fn get_file_name(path: &Path) -> String {
-    path.file_name().map_or_else(
-        || String::from("-"),
-        |file_name| {
-            file_name
-                .to_str()
-                .map_or_else(|| String::from("-"), ToString::to_string)
-        },
-    )
+pub fn get_file_name(path: &Path) -> OsString {
+    path.file_name()
+        .map_or_else(|| OsString::from("-"), ToOwned::to_owned)
 }
 
+/// Names starting with a dot are hidden, no matter if they are valid UTF-8
 #[must_use]
-pub fn get_created_time(path: &Path) -> String {
-    let time = path
-        .metadata()
-        .map_or(DateTime::<Utc>::default(), |metadata| {
-            metadata
-                .created()
-                .map_or(DateTime::<Utc>::default(), |time| {
-                    DateTime::<Utc>::from(time)
-                })
-        });
+pub fn is_hidden(name: &OsStr) -> bool {
+    name.as_bytes().first() == Some(&b'.')
+}
 
-    format!(
-        "{: >3} {: >2} {: >2}:{: <2}",
-        DATE_MAPPER
-            .iter()
-            .nth(time.month().try_into().map_or(0, |index| index))
-            .map_or("-", |month| month),
-        time.day(),
-        time.hour(),
-        time.minute()
-    )
+/// Number of terminal columns the text takes up, counted per grapheme cluster
+/// so that combining characters and joined emoji are not counted twice
+#[must_use]
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true)
+        .map(|grapheme| grapheme.width().min(2))
+        .sum()
+}
+
+/// The device id as `major:minor`, split the way glibc does
+#[must_use]
+pub fn format_device(device: u64) -> String {
+    let major = ((device >> 8) & 0xfff) | ((device >> 32) & 0xffff_f000);
+    let minor = (device & 0xff) | ((device >> 12) & 0xffff_ff00);
+
+    format!("{major}:{minor}")
+}
+
+/// The size in the largest unit that keeps it above one, rounded down, like `3k`
+#[must_use]
+pub fn format_size(size: u64) -> String {
+    let mut new_size = size;
+    let mut post = 0;
+    while new_size >= 1024 {
+        new_size /= 1024;
+        post += 1;
+    }
+
+    format!("{}{}", new_size, SIZE_HELPER[post])
 }
 
+/// The time as month, day, hour and minute, the Unix epoch when it is unknown
 #[must_use]
-pub fn get_modified_time(path: &Path) -> String {
-    let time = path
-        .metadata()
-        .map_or(DateTime::<Utc>::default(), |metadata| {
-            metadata
-                .modified()
-                .map_or(DateTime::<Utc>::default(), |time| {
-                    DateTime::<Utc>::from(time)
-                })
-        });
+pub fn format_time(time: Option<SystemTime>) -> String {
+    let time = time.map_or_else(DateTime::<Utc>::default, DateTime::<Utc>::from);
 
     format!(
         "{: >3} {: >2} {: >2}:{: <2}",
         DATE_MAPPER
-            .iter()
-            .nth(time.month().try_into().map_or(0, |index| index))
+            .get(usize::try_from(time.month()).map_or(0, |index| index))
             .map_or("-", |month| month),
         time.day(),
         time.hour(),
@@ -96,22 +111,12 @@ pub fn get_modified_time(path: &Path) -> String {
 
 #[cfg(test)]
 mod tests {
-    use std::fs::File;
-    use std::io::Write;
-    use std::path::PathBuf;
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt, path::PathBuf, time::SystemTime};
 
-    use crate::support::{get_created_time, get_file_name, get_modified_time};
-
-    fn create_temp_file_with_permissions() -> PathBuf {
-        let temp_dir = tempfile::tempdir().expect("Failed to create temporary directory");
-        let file_path = temp_dir.path().join("test_file.txt");
-        let mut file = File::create(&file_path).expect("Failed to create temporary file");
-
-        file.write_all(b"Hello, World!")
-            .expect("Failed to write to file");
-
-        file_path
-    }
+    use crate::support::{
+        display_width, format_device, format_permissions, format_size, format_time, get_file_name,
+        is_hidden,
+    };
 
     #[test]
     fn test_get_file_name() {
@@ -121,20 +126,67 @@ mod tests {
     }
 
     #[test]
-    fn test_get_created_time() {
-        let file_path = create_temp_file_with_permissions();
+    fn test_get_file_name_invalid_utf8() {
+        let file_path = PathBuf::from(OsStr::from_bytes(b"/path/to/\xffile"));
+        let file_name = get_file_name(&file_path);
+        assert_eq!(file_name.as_bytes(), b"\xffile");
+    }
+
+    #[test]
+    fn test_is_hidden() {
+        assert!(is_hidden(OsStr::new(".git")));
+        assert!(is_hidden(OsStr::from_bytes(b".\xff")));
+        assert!(!is_hidden(OsStr::new("src")));
+    }
+
+    #[test]
+    fn test_format_permissions() {
+        assert_eq!(format_permissions(0o100_644), "-rw-r--r--");
+        assert_eq!(format_permissions(0o040_755), "drwxr-xr-x");
+        assert_eq!(format_permissions(0o120_777), "lrwxrwxrwx");
+        assert_eq!(format_permissions(0o010_600), "prw-------");
+    }
 
-        let created_time = get_created_time(&file_path);
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0b");
+        assert_eq!(format_size(1024), "1k");
+        assert_eq!(format_size(4000), "3k");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5g");
+    }
 
-        assert!(!created_time.is_empty());
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(Some(SystemTime::UNIX_EPOCH)), "Jan  1  0:0 ");
+        assert_eq!(format_time(None), "Jan  1  0:0 ");
+        assert!(!format_time(Some(SystemTime::now())).is_empty());
     }
 
     #[test]
-    fn test_get_modified_time() {
-        let file_path = create_temp_file_with_permissions();
+    fn test_display_width_ascii() {
+        assert_eq!(display_width("file.txt"), 8);
+    }
 
-        let modified_time = get_modified_time(&file_path);
+    #[test]
+    fn test_display_width_wide() {
+        assert_eq!(display_width("\u{6587}\u{4ef6}.txt"), 8);
+        assert_eq!(display_width("\u{1f980}.rs"), 5);
+    }
 
-        assert!(!modified_time.is_empty());
+    #[test]
+    fn test_display_width_combining() {
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(
+            display_width("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"),
+            2
+        );
+    }
+
+    #[test]
+    fn test_format_device() {
+        assert_eq!(format_device(0x0803), "8:3");
+        assert_eq!(format_device(0x0001_0305), "259:5");
+        assert_eq!(format_device(0x1000_0010_0000), "4096:256");
+        assert_eq!(format_device(0), "0:0");
     }
 }